@@ -0,0 +1,399 @@
+// A small expression language, for writing paths like `sin(t) + 2 * sin(2 * t)` in scene files.
+//
+// It has numbers, the variable `t`, the constants `pi` and `tau`, the operators `+ - * / ^` with the usual precedence,
+// parentheses, and the functions `sin`, `cos`, `tan`, `sqrt`, `abs`, `exp` and `ln`.
+
+use std::fmt;
+
+// How deeply parentheses, calls, negations and powers can nest, so that the parser's recursion can't overflow the stack.
+const MAX_DEPTH: usize = 100;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expr {
+    Number(f64),
+    T,
+    Neg(Box<Expr>),
+    Binary(Op, Box<Expr>, Box<Expr>),
+    Call(Function, Box<Expr>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Function {
+    Sin,
+    Cos,
+    Tan,
+    Sqrt,
+    Abs,
+    Exp,
+    Ln,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseError {
+    // Counting characters from 1.
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "column {}: {}", self.column, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl Expr {
+    pub fn parse(source: &str) -> Result<Self, ParseError> {
+        let mut parser = Parser {
+            tokens: tokenize(source)?,
+            next: 0,
+            depth: 0,
+        };
+        let expr = parser.expr()?;
+        match parser.peek() {
+            (_, Token::End) => Ok(expr),
+            (column, Token::Close) => Err(ParseError {
+                column,
+                message: "unmatched `)`".to_string(),
+            }),
+            (column, _) => Err(ParseError {
+                column,
+                message: "expected an operator".to_string(),
+            }),
+        }
+    }
+
+    pub fn eval(&self, t: f64) -> f64 {
+        match self {
+            Expr::Number(x) => *x,
+            Expr::T => t,
+            Expr::Neg(a) => -a.eval(t),
+            Expr::Binary(op, a, b) => {
+                let (a, b) = (a.eval(t), b.eval(t));
+                match op {
+                    Op::Add => a + b,
+                    Op::Sub => a - b,
+                    Op::Mul => a * b,
+                    Op::Div => a / b,
+                    Op::Pow => a.powf(b),
+                }
+            }
+            Expr::Call(function, a) => {
+                let a = a.eval(t);
+                match function {
+                    Function::Sin => a.sin(),
+                    Function::Cos => a.cos(),
+                    Function::Tan => a.tan(),
+                    Function::Sqrt => a.sqrt(),
+                    Function::Abs => a.abs(),
+                    Function::Exp => a.exp(),
+                    Function::Ln => a.ln(),
+                }
+            }
+        }
+    }
+}
+
+impl std::str::FromStr for Expr {
+    type Err = ParseError;
+
+    fn from_str(source: &str) -> Result<Self, ParseError> {
+        Self::parse(source)
+    }
+}
+
+// In scene files, expressions are strings.
+impl<'de> serde::Deserialize<'de> for Expr {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let source = String::deserialize(deserializer)?;
+        Self::parse(&source)
+            .map_err(|e| serde::de::Error::custom(format!("in `{}`, at {}", source, e)))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Number(f64),
+    Name(String),
+    Op(Op),
+    Open,
+    Close,
+    End,
+}
+
+// Each token, with the column it starts at.
+fn tokenize(source: &str) -> Result<Vec<(usize, Token)>, ParseError> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let column = i + 1;
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        let token = if c.is_ascii_digit() || c == '.' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            // An exponent, as in `1e-3`.
+            if i < chars.len() && (chars[i] == 'e' || chars[i] == 'E') {
+                let mut j = i + 1;
+                if j < chars.len() && (chars[j] == '+' || chars[j] == '-') {
+                    j += 1;
+                }
+                if j < chars.len() && chars[j].is_ascii_digit() {
+                    i = j;
+                    while i < chars.len() && chars[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                }
+            }
+            let text: String = chars[start..i].iter().collect();
+            match text.parse() {
+                Ok(x) => Token::Number(x),
+                Err(_) => {
+                    return Err(ParseError {
+                        column,
+                        message: format!("`{}` is not a number", text),
+                    })
+                }
+            }
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            Token::Name(chars[start..i].iter().collect())
+        } else {
+            i += 1;
+            match c {
+                '+' => Token::Op(Op::Add),
+                '-' => Token::Op(Op::Sub),
+                '*' => Token::Op(Op::Mul),
+                '/' => Token::Op(Op::Div),
+                '^' => Token::Op(Op::Pow),
+                '(' => Token::Open,
+                ')' => Token::Close,
+                _ => {
+                    return Err(ParseError {
+                        column,
+                        message: format!("unexpected `{}`", c),
+                    })
+                }
+            }
+        };
+        tokens.push((column, token));
+    }
+    tokens.push((chars.len() + 1, Token::End));
+    Ok(tokens)
+}
+
+// A recursive descent parser, one method per level of precedence.
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    next: usize,
+    // How many calls to `unary` are under way. Every level of nesting goes through it.
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> (usize, Token) {
+        self.tokens[self.next].clone()
+    }
+
+    fn advance(&mut self) -> (usize, Token) {
+        let token = self.peek();
+        if token.1 != Token::End {
+            self.next += 1;
+        }
+        token
+    }
+
+    // Sums and differences.
+    fn expr(&mut self) -> Result<Expr, ParseError> {
+        let mut a = self.term()?;
+        while let (_, Token::Op(op @ Op::Add)) | (_, Token::Op(op @ Op::Sub)) = self.peek() {
+            self.advance();
+            a = Expr::Binary(op, Box::new(a), Box::new(self.term()?));
+        }
+        Ok(a)
+    }
+
+    // Products and quotients.
+    fn term(&mut self) -> Result<Expr, ParseError> {
+        let mut a = self.unary()?;
+        while let (_, Token::Op(op @ Op::Mul)) | (_, Token::Op(op @ Op::Div)) = self.peek() {
+            self.advance();
+            a = Expr::Binary(op, Box::new(a), Box::new(self.unary()?));
+        }
+        Ok(a)
+    }
+
+    // Negation, counting how deeply it's nested.
+    fn unary(&mut self) -> Result<Expr, ParseError> {
+        if self.depth == MAX_DEPTH {
+            return Err(ParseError {
+                column: self.peek().0,
+                message: "nested too deeply".to_string(),
+            });
+        }
+        self.depth += 1;
+        let a = self.negation();
+        self.depth -= 1;
+        a
+    }
+
+    // As in mathematics, `-t^2` is `-(t^2)`.
+    fn negation(&mut self) -> Result<Expr, ParseError> {
+        if let (_, Token::Op(Op::Sub)) = self.peek() {
+            self.advance();
+            return Ok(Expr::Neg(Box::new(self.unary()?)));
+        }
+        self.power()
+    }
+
+    // Powers, which group to the right: `2^3^2` is `2^(3^2)`.
+    fn power(&mut self) -> Result<Expr, ParseError> {
+        let a = self.atom()?;
+        if let (_, Token::Op(Op::Pow)) = self.peek() {
+            self.advance();
+            return Ok(Expr::Binary(Op::Pow, Box::new(a), Box::new(self.unary()?)));
+        }
+        Ok(a)
+    }
+
+    fn atom(&mut self) -> Result<Expr, ParseError> {
+        let (column, token) = self.advance();
+        match token {
+            Token::Number(x) => Ok(Expr::Number(x)),
+            Token::Open => {
+                let a = self.expr()?;
+                self.close(column)?;
+                Ok(a)
+            }
+            Token::Name(name) => {
+                if let (open, Token::Open) = self.peek() {
+                    let function = match &name[..] {
+                        "sin" => Function::Sin,
+                        "cos" => Function::Cos,
+                        "tan" => Function::Tan,
+                        "sqrt" => Function::Sqrt,
+                        "abs" => Function::Abs,
+                        "exp" => Function::Exp,
+                        "ln" => Function::Ln,
+                        _ => {
+                            return Err(ParseError {
+                                column,
+                                message: format!("unknown function `{}`", name),
+                            })
+                        }
+                    };
+                    self.advance();
+                    let a = self.expr()?;
+                    self.close(open)?;
+                    return Ok(Expr::Call(function, Box::new(a)));
+                }
+                match &name[..] {
+                    "t" => Ok(Expr::T),
+                    "pi" => Ok(Expr::Number(std::f64::consts::PI)),
+                    "tau" => Ok(Expr::Number(std::f64::consts::TAU)),
+                    _ => Err(ParseError {
+                        column,
+                        message: format!("unknown variable `{}`", name),
+                    }),
+                }
+            }
+            Token::End => Err(ParseError {
+                column,
+                message: "unexpected end of expression".to_string(),
+            }),
+            _ => Err(ParseError {
+                column,
+                message: "expected a number, `t`, a function or `(`".to_string(),
+            }),
+        }
+    }
+
+    // The `)` matching the `(` at column `open`.
+    fn close(&mut self, open: usize) -> Result<(), ParseError> {
+        match self.advance() {
+            (_, Token::Close) => Ok(()),
+            (column, _) => Err(ParseError {
+                column,
+                message: format!("expected `)` to match the `(` at column {}", open),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(source: &str, t: f64) -> f64 {
+        Expr::parse(source).unwrap().eval(t)
+    }
+
+    fn error(source: &str) -> ParseError {
+        Expr::parse(source).unwrap_err()
+    }
+
+    #[test]
+    fn arithmetic() {
+        assert_eq!(eval("1 + 2 * 3", 0.), 7.);
+        assert_eq!(eval("(1 + 2) * 3", 0.), 9.);
+        assert_eq!(eval("8 / 2 / 2", 0.), 2.);
+        assert_eq!(eval("10 - 4 - 3", 0.), 3.);
+        assert_eq!(eval("2 ^ 3 ^ 2", 0.), 512.);
+        assert_eq!(eval("-t^2", 3.), -9.);
+        assert_eq!(eval("2 * -t", 3.), -6.);
+        assert_eq!(eval("1.5e1 + .5", 0.), 15.5);
+    }
+
+    #[test]
+    fn functions() {
+        let t = 0.7f64;
+        assert_eq!(
+            eval("sin(t) + 2 * sin(2 * t)", t),
+            t.sin() + 2. * (2. * t).sin()
+        );
+        assert_eq!(eval("cos(pi)", 0.), -1.);
+        assert_eq!(eval("sqrt(abs(-16))", 0.), 4.);
+        assert_eq!(eval("ln(exp(2))", 0.), 2.);
+        assert_eq!(eval("tau / 2", 0.), std::f64::consts::PI);
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(error("1 + ").column, 5);
+        assert_eq!(error("2 % 3").column, 3);
+        assert_eq!(error("sin(t").column, 6);
+        assert!(error("sin(t").message.contains("column 4"));
+        assert_eq!(error("t)").message, "unmatched `)`");
+        assert_eq!(error("2 t").message, "expected an operator");
+        assert_eq!(error("sine(t)").message, "unknown function `sine`");
+        assert_eq!(error("x + 1").message, "unknown variable `x`");
+        assert_eq!(error("1.2.3").message, "`1.2.3` is not a number");
+        assert_eq!(error("* 2").column, 1);
+
+        let nested = |depth: usize| format!("{}t{}", "sin(".repeat(depth), ")".repeat(depth));
+        assert_eq!(eval(&nested(MAX_DEPTH - 1), 0.), 0.);
+        assert_eq!(error(&nested(MAX_DEPTH)).message, "nested too deeply");
+        assert_eq!(error(&"-".repeat(100_000)).column, MAX_DEPTH + 1);
+        assert_eq!(error(&"(".repeat(100_000)).message, "nested too deeply");
+    }
+}
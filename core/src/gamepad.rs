@@ -0,0 +1,213 @@
+// Gamepads, as the browser's Gamepad API describes them with the standard mapping.
+//
+// The front-end reads the gamepad's state every frame, and `Gamepad::poll` turns the changes into the same `Input`s as the keyboard and mouse:
+// each button is a key named `Gamepad0`, `Gamepad1` and so on, which the bindings can use,
+// and the sticks and triggers are `Input::Analog`, after the dead zone and the response curve.
+
+use crate::replay::{Analog, Input};
+use crate::settings::Settings;
+use serde::{Deserialize, Serialize};
+
+// Indices in the standard mapping.
+const LEFT_X: usize = 0;
+const LEFT_Y: usize = 1;
+const RIGHT_X: usize = 2;
+const RIGHT_Y: usize = 3;
+const LEFT_TRIGGER: usize = 6;
+const RIGHT_TRIGGER: usize = 7;
+
+// A button counts as pressed when it's pressed this far.
+const PRESSED: f32 = 0.5;
+
+/// A gamepad's state at one moment, as `Gamepad.axes` and each `GamepadButton.value` give it.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct State {
+    // From -1 to 1. Up is negative.
+    pub axes: Vec<f32>,
+    // From 0 to 1.
+    pub buttons: Vec<f32>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GamepadSettings {
+    // Sticks tilted less than this, and triggers pressed less than this, count as centred.
+    pub deadzone: f32,
+    // Past the dead zone, the response is the tilt to this power, so that small movements are more precise.
+    pub curve: f32,
+    // How fast the right stick turns, at full tilt, in radians per second.
+    pub look_speed: f32,
+}
+
+impl Default for GamepadSettings {
+    fn default() -> Self {
+        Self {
+            deadzone: 0.15,
+            curve: 2.,
+            look_speed: 2.5,
+        }
+    }
+}
+
+/// Remembers the last state, so as to report only what changed.
+#[derive(Default)]
+pub struct Gamepad {
+    pressed: Vec<bool>,
+    analog: Analog,
+}
+
+impl Gamepad {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The input since the last poll. With no gamepad connected, poll with `State::default()`, which releases everything.
+    pub fn poll(&mut self, state: &State, settings: &Settings) -> Vec<Input> {
+        let mut out = Vec::new();
+
+        let count = self.pressed.len().max(state.buttons.len());
+        self.pressed.resize(count, false);
+        for (b, pressed) in self.pressed.iter_mut().enumerate() {
+            let now = state.buttons.get(b).is_some_and(|&value| value >= PRESSED);
+            if now != *pressed {
+                let key = format!("Gamepad{}", b);
+                out.push(if now {
+                    Input::KeyDown(key)
+                } else {
+                    Input::KeyUp(key)
+                });
+                *pressed = now;
+            }
+        }
+
+        let analog = analog(state, settings);
+        if analog != self.analog {
+            out.push(Input::Analog(analog));
+            self.analog = analog;
+        }
+
+        out
+    }
+}
+
+fn analog(state: &State, settings: &Settings) -> Analog {
+    let gamepad = &settings.gamepad;
+    let axis = |i: usize| state.axes.get(i).copied().unwrap_or(0.);
+    let button = |i: usize| state.buttons.get(i).copied().unwrap_or(0.);
+
+    let [right, forward] = stick([axis(LEFT_X), -axis(LEFT_Y)], gamepad);
+    let [turn, up] = stick([axis(RIGHT_X), -axis(RIGHT_Y)], gamepad);
+    let up = if settings.invert_y { -up } else { up };
+
+    Analog {
+        movement: [right, forward],
+        look: [turn * gamepad.look_speed, up * gamepad.look_speed],
+        vertical: response(button(RIGHT_TRIGGER), gamepad)
+            - response(button(LEFT_TRIGGER), gamepad),
+    }
+}
+
+// A stick's position, with a round dead zone, keeping its direction.
+fn stick([x, y]: [f32; 2], settings: &GamepadSettings) -> [f32; 2] {
+    let tilt = (x * x + y * y).sqrt();
+    let scaled = response(tilt, settings);
+    if scaled == 0. {
+        return [0., 0.];
+    }
+    [x / tilt * scaled, y / tilt * scaled]
+}
+
+// How strongly to respond to a stick tilted, or a trigger pressed, this far.
+fn response(amount: f32, settings: &GamepadSettings) -> f32 {
+    if amount <= settings.deadzone {
+        return 0.;
+    }
+    let amount = ((amount - settings.deadzone) / (1. - settings.deadzone)).min(1.);
+    amount.powf(settings.curve)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(axes: [f32; 4], buttons: &[f32]) -> State {
+        State {
+            axes: axes.to_vec(),
+            buttons: buttons.to_vec(),
+        }
+    }
+
+    #[test]
+    fn dead_zone_and_curve() {
+        let settings = Settings::default();
+        let analog = |axes| analog(&state(axes, &[]), &settings);
+
+        assert_eq!(analog([0.1, -0.1, 0.05, 0.]), Analog::default());
+
+        // Pushed all the way up and to the right.
+        let a = analog([0.8, -0.8, 0., 0.]);
+        assert!((a.movement[0] - a.movement[1]).abs() < 1e-6);
+        assert!((a.movement[0] - std::f32::consts::FRAC_1_SQRT_2).abs() < 1e-6);
+
+        // Halfway past the dead zone, with the default curve of 2.
+        let a = analog([0., 0., 0.575, 0.]);
+        assert!((a.look[0] - 0.25 * settings.gamepad.look_speed).abs() < 1e-5);
+        assert_eq!(a.look[1], 0.);
+
+        let linear = Settings {
+            gamepad: GamepadSettings {
+                curve: 1.,
+                ..GamepadSettings::default()
+            },
+            ..Settings::default()
+        };
+        let a = super::analog(
+            &state([0.; 4], &[0., 0., 0., 0., 0., 0., 0., 0.575]),
+            &linear,
+        );
+        assert!((a.vertical - 0.5).abs() < 1e-5);
+    }
+
+    #[test]
+    fn buttons_are_keys() {
+        let settings = Settings::default();
+        let mut gamepad = Gamepad::new();
+        assert_eq!(
+            gamepad.poll(&state([0.; 4], &[1., 0., 0.2]), &settings),
+            [Input::KeyDown("Gamepad0".to_string())]
+        );
+        assert_eq!(gamepad.poll(&state([0.; 4], &[1., 0., 0.2]), &settings), []);
+        assert_eq!(
+            gamepad.poll(&state([0.; 4], &[0., 0., 0.9]), &settings),
+            [
+                Input::KeyUp("Gamepad0".to_string()),
+                Input::KeyDown("Gamepad2".to_string())
+            ]
+        );
+
+        // Disconnected.
+        assert_eq!(
+            gamepad.poll(&State::default(), &settings),
+            [Input::KeyUp("Gamepad2".to_string())]
+        );
+    }
+
+    #[test]
+    fn sticks_change_only_when_they_move() {
+        let settings = Settings::default();
+        let mut gamepad = Gamepad::new();
+        let inputs = gamepad.poll(&state([0., -1., 0., 0.], &[]), &settings);
+        assert_eq!(
+            inputs,
+            [Input::Analog(Analog {
+                movement: [0., 1.],
+                ..Analog::default()
+            })]
+        );
+        assert_eq!(gamepad.poll(&state([0., -1., 0., 0.], &[]), &settings), []);
+        assert_eq!(
+            gamepad.poll(&State::default(), &settings),
+            [Input::Analog(Analog::default())]
+        );
+    }
+}
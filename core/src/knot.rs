@@ -0,0 +1,691 @@
+// A knot, described by a closed parametric curve `t -> (x, y, z)` with period 2π.
+// Or a link of several knots, each described by its own curve.
+//
+// Everything the portal needs is worked out from the curves:
+//     - The crossings of their projection onto the xy-plane, and which strand passes over at each.
+//     - The arcs of the diagram, which run from one undercrossing to the next.
+//     - Which arcs a straight line passes under, and in what order.
+//
+// The parameters of the components are laid end to end: component `c`'s curve at `t` is the point with parameter `c * 2π + t`.
+//
+// The curves should be in general position: their projection should have no triple points or tangencies.
+
+use nalgebra::{Vector2, Vector3};
+
+const TAU: f64 = 2. * std::f64::consts::PI;
+
+// Each curve is sampled as a polyline, to find candidates quickly; each candidate is then refined on the curve itself.
+// The chunks' bounding boxes let us skip most of the polyline.
+const CHUNK: usize = 32;
+const CHUNKS: usize = 64;
+const SAMPLES: usize = CHUNK * CHUNKS;
+
+// How far the curve can bulge away from its polyline.
+const MARGIN: f64 = 1e-3;
+
+// How many vertices the shader's polyline of the link has room for. This must match the constant in `fragment_prelude.glsl`.
+pub const SHADER_VERTICES: usize = 144;
+
+// The fewest samples `polyline` takes of each component, on top of its undercrossings.
+// Fewer would leave a component with no undercrossings as a point or a line.
+const MIN_POLYLINE_SAMPLES: usize = 3;
+
+pub type Curve = Box<dyn Fn(f64) -> Vector3<f64> + Send + Sync>;
+
+pub struct Crossing {
+    pub pos: Vector2<f64>,
+    // Curve parameters of the two strands.
+    pub over: f64,
+    pub under: f64,
+
+    pub over_arc: usize,
+    // The under strand leaves `arc_in` and enters `arc_out` here.
+    pub arc_in: usize,
+    pub arc_out: usize,
+}
+
+pub struct Knot {
+    curves: Vec<Curve>,
+
+    // `points[i]` is the link at parameter `param(i)`. Each component has `SAMPLES` of them.
+    points: Vec<Vector3<f64>>,
+    // The xy bounding box of each chunk of the polyline.
+    boxes: Vec<(Vector2<f64>, Vector2<f64>)>,
+
+    // The parameters where the arcs start, in increasing order. Arc `k` starts at `cuts[k]`.
+    // These are the undercrossings, and the start of each component that never passes under anything.
+    cuts: Vec<f64>,
+    crossings: Vec<Crossing>,
+}
+
+impl Knot {
+    pub fn new(curve: impl Fn(f64) -> Vector3<f64> + Send + Sync + 'static) -> Self {
+        Self::link(vec![Box::new(curve)])
+    }
+
+    pub fn link(curves: Vec<Curve>) -> Self {
+        assert!(!curves.is_empty(), "a link needs at least one component");
+        let samples = curves.len() * SAMPLES;
+
+        let mut knot = Self {
+            curves,
+            points: Vec::new(),
+            boxes: Vec::new(),
+            cuts: Vec::new(),
+            crossings: Vec::new(),
+        };
+
+        knot.points = (0..samples).map(|i| knot.point(param(i))).collect();
+        knot.boxes = (0..samples / CHUNK)
+            .map(|c| {
+                bounding_box(
+                    (c * CHUNK..(c + 1) * CHUNK)
+                        .flat_map(|i| knot.segment(i))
+                        .map(|p| p.xy()),
+                )
+            })
+            .collect();
+
+        // Find the crossings, as pairs of curve parameters.
+        let mut pairs: Vec<(f64, f64)> = Vec::new();
+        for i in 0..samples {
+            let [a, b] = knot.segment(i);
+            for j in knot.candidates(a.xy(), b.xy()).filter(|&j| j > i) {
+                if knot.next(i) == j || knot.next(j) == i {
+                    continue;
+                }
+                let [p, q] = knot.segment(j);
+                if let Some((s, u)) = intersect(a.xy(), b.xy(), p.xy(), q.xy()) {
+                    let pair = knot.refine_crossing(
+                        param(i) + s * (TAU / SAMPLES as f64),
+                        param(j) + u * (TAU / SAMPLES as f64),
+                    );
+                    if !pairs
+                        .iter()
+                        .any(|&(t1, t2)| (t1 - pair.0).abs() < 1e-9 && (t2 - pair.1).abs() < 1e-9)
+                    {
+                        pairs.push(pair);
+                    }
+                }
+            }
+        }
+
+        let pairs: Vec<(f64, f64, Vector2<f64>)> = pairs
+            .into_iter()
+            .map(|(t1, t2)| {
+                let (p1, p2) = (knot.point(t1), knot.point(t2));
+                let pos = 0.5 * (p1.xy() + p2.xy());
+                if p1.z > p2.z {
+                    (t1, t2, pos)
+                } else {
+                    (t2, t1, pos)
+                }
+            })
+            .collect();
+
+        knot.cuts = pairs.iter().map(|&(_, under, _)| under).collect();
+        for c in 0..knot.components() {
+            if !knot.cuts.iter().any(|&cut| knot.component(cut) == c) {
+                knot.cuts.push(c as f64 * TAU);
+            }
+        }
+        knot.cuts.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        let crossings = pairs
+            .into_iter()
+            .map(|(over, under, pos)| {
+                let arc_out = knot.arc(under);
+                Crossing {
+                    pos,
+                    over,
+                    under,
+                    over_arc: knot.arc(over),
+                    arc_in: knot.previous_arc(arc_out),
+                    arc_out,
+                }
+            })
+            .collect();
+        knot.crossings = crossings;
+
+        knot
+    }
+
+    // The trefoil the portal was originally built around.
+    pub fn trefoil() -> Self {
+        Self::new(|t| {
+            Vector3::new(
+                t.sin() + 2. * (2. * t).sin(),
+                t.cos() - 2. * (2. * t).cos(),
+                (3. * t).sin(),
+            )
+        })
+    }
+
+    pub fn figure_eight() -> Self {
+        Self::new(|t| {
+            let r = 2. + (2. * t).cos();
+            Vector3::new(r * (3. * t).cos(), r * (3. * t).sin(), (4. * t).sin())
+        })
+    }
+
+    // Winds `p` times around the z-axis, and `q` times around the core of a torus.
+    // `torus(2, 3)` is a trefoil, and `torus(2, 5)` is a cinquefoil.
+    pub fn torus(p: u32, q: u32) -> Self {
+        let (p, q) = (f64::from(p), f64::from(q));
+        Self::new(move |t| {
+            let r = 2. + (q * t).cos();
+            Vector3::new(r * (p * t).cos(), r * (p * t).sin(), (q * t).sin())
+        })
+    }
+
+    // The Hopf link: two circles, each passing once through the other.
+    pub fn hopf() -> Self {
+        Self::link(vec![
+            Box::new(|t: f64| Vector3::new(2. * t.cos() - 1., 2. * t.sin(), 0.8 * t.sin())),
+            Box::new(|t: f64| Vector3::new(2. * t.cos() + 1., 2. * t.sin(), -0.8 * t.sin())),
+        ])
+    }
+
+    // The Borromean rings: three circles, each passing over the next and under the one before.
+    // No two of the rings are linked, but all three together are.
+    pub fn borromean() -> Self {
+        let ring = |k: u32| -> Curve {
+            let angle = std::f64::consts::FRAC_PI_2 + f64::from(k) * TAU / 3.;
+            Box::new(move |t: f64| {
+                Vector3::new(
+                    angle.cos() + 1.6 * t.cos(),
+                    angle.sin() + 1.6 * t.sin(),
+                    0.8 * (3. * t).cos(),
+                )
+            })
+        };
+        Self::link(vec![ring(0), ring(1), ring(2)])
+    }
+
+    // The Whitehead link: a figure-of-eight curve, with a circle threaded through both of its loops.
+    // The two components have linking number zero, but can't be pulled apart.
+    pub fn whitehead() -> Self {
+        Self::link(vec![
+            Box::new(|t: f64| {
+                Vector3::new(
+                    2.5 * t.sin(),
+                    1.2 * (2. * t).sin(),
+                    0.6 * t.cos() - 2.1 * t.sin() * (2. * t).sin(),
+                )
+            }),
+            Box::new(|t: f64| Vector3::new(1.5 * t.cos(), 2. * t.sin(), 0.5 * (2. * t).sin())),
+        ])
+    }
+
+    pub fn components(&self) -> usize {
+        self.curves.len()
+    }
+
+    // Which component is the point with parameter `t` on?
+    pub fn component(&self, t: f64) -> usize {
+        ((t / TAU).floor().max(0.) as usize).min(self.components() - 1)
+    }
+
+    pub fn point(&self, t: f64) -> Vector3<f64> {
+        let c = self.component(t);
+        self.point_on(c, t - c as f64 * TAU)
+    }
+
+    pub fn tangent(&self, t: f64) -> Vector3<f64> {
+        let c = self.component(t);
+        self.tangent_on(c, t - c as f64 * TAU)
+    }
+
+    // The parameter of the point at `t` on component `c`'s own curve.
+    pub fn param_on(&self, c: usize, t: f64) -> f64 {
+        let start = c as f64 * TAU;
+        let t = start + t.rem_euclid(TAU);
+        // Rounding can land us on the start of the next component. That's the same point as the start of this one.
+        if t >= start + TAU {
+            start
+        } else {
+            t
+        }
+    }
+
+    // Component `c`'s own curve. Near the ends of a component's range of parameters,
+    // this avoids rounding onto the neighbouring component.
+    fn point_on(&self, c: usize, t: f64) -> Vector3<f64> {
+        (self.curves[c])(t)
+    }
+
+    fn tangent_on(&self, c: usize, t: f64) -> Vector3<f64> {
+        const H: f64 = 1e-5;
+        (self.point_on(c, t + H) - self.point_on(c, t - H)) / (2. * H)
+    }
+
+    pub fn crossings(&self) -> &[Crossing] {
+        &self.crossings
+    }
+
+    // The number of arcs in the diagram. A component that never passes under anything is a single arc.
+    pub fn arcs(&self) -> usize {
+        self.cuts.len()
+    }
+
+    // Which arc is the point with parameter `t` on?
+    pub fn arc(&self, t: f64) -> usize {
+        let c = self.component(t);
+        self.arc_on(c, t - c as f64 * TAU)
+    }
+
+    fn arc_on(&self, c: usize, t: f64) -> usize {
+        let arcs = self.component_arcs(c);
+        let start = c as f64 * TAU;
+        match self.cuts[arcs.clone()]
+            .iter()
+            .rposition(|&cut| cut - start <= t)
+        {
+            Some(k) => arcs.start + k,
+            None => arcs.end - 1,
+        }
+    }
+
+    // The arcs of component `c`, in order.
+    pub fn component_arcs(&self, c: usize) -> std::ops::Range<usize> {
+        let start = self
+            .cuts
+            .iter()
+            .position(|&cut| self.component(cut) >= c)
+            .unwrap();
+        let end = self
+            .cuts
+            .iter()
+            .rposition(|&cut| self.component(cut) <= c)
+            .unwrap()
+            + 1;
+        start..end
+    }
+
+    // The arc before arc `k`, along its component.
+    fn previous_arc(&self, k: usize) -> usize {
+        let arcs = self.component_arcs(self.component(self.cuts[k]));
+        if k == arcs.start {
+            arcs.end - 1
+        } else {
+            k - 1
+        }
+    }
+
+    // The link as a polyline of at most `n` vertices, for the shader.
+    // Each vertex is paired with the arc of the segment starting there. Each component ends with a copy of its first vertex,
+    // paired with `None`, as no segment starts there. The undercrossings are vertices, so that each segment lies in a single arc.
+    // Returns `None` if the undercrossings leave too few of the `n` vertices for the rest of the link.
+    pub fn polyline(&self, n: usize) -> Option<Vec<(Vector3<f64>, Option<usize>)>> {
+        let samples = (n.checked_sub(self.cuts.len())? / self.components()).checked_sub(1)?;
+        if samples < MIN_POLYLINE_SAMPLES {
+            return None;
+        }
+
+        let polyline = (0..self.components())
+            .flat_map(|c| {
+                let start = c as f64 * TAU;
+                let mut params: Vec<f64> = (0..samples)
+                    .map(|i| start + i as f64 * TAU / samples as f64)
+                    .chain(
+                        self.cuts
+                            .iter()
+                            .cloned()
+                            .filter(|&cut| self.component(cut) == c),
+                    )
+                    .collect();
+                params.sort_by(|a, b| a.partial_cmp(b).unwrap());
+                params.dedup();
+
+                let mut vertices: Vec<(Vector3<f64>, Option<usize>)> = params
+                    .into_iter()
+                    .map(|t| (self.point(t), Some(self.arc(t))))
+                    .collect();
+                vertices.push((vertices[0].0, None));
+                vertices
+            })
+            .collect();
+        Some(polyline)
+    }
+
+    // If you travel in a straight line from `start` to `end`, which arcs do you pass under?
+    // Returns how far along the line each happens, in increasing order, along with the arc,
+    // and whether you pass from its left to its right (looking down on it, along the direction of increasing parameter).
+    //
+    // The line is treated as half-open, so that a crossing at the shared point of consecutive segments of a path is only counted once.
+    pub fn passes_under(&self, start: Vector3<f64>, end: Vector3<f64>) -> Vec<(f64, usize, bool)> {
+        let d = (end - start).xy();
+        if d == Vector2::zeros() {
+            return Vec::new();
+        }
+
+        // `side(p)` is positive on one side of the line through `start` and `end`, and negative on the other.
+        let side = |p: Vector3<f64>| d.perp(&(p - start).xy());
+
+        let mut out: Vec<(f64, usize, bool)> = self
+            .candidates(start.xy(), end.xy())
+            .filter_map(|i| {
+                let [p, q] = self.segment(i);
+                if (side(p) > 0.) == (side(q) > 0.) {
+                    return None;
+                }
+
+                // Before bisecting, rule out crossings that are certainly off the ends of the line, or certainly over the curve.
+                let along = |p: Vector3<f64>| d.dot(&(p - start).xy()) / d.norm_squared();
+                let slack = MARGIN / d.norm();
+                let s_lo = along(p).min(along(q)) - slack;
+                let s_hi = along(p).max(along(q)) + slack;
+                if s_hi < 0. || s_lo >= 1. {
+                    return None;
+                }
+                let line_z = |s: f64| start.z + s.clamp(0., 1.) * (end.z - start.z);
+                if line_z(s_lo).min(line_z(s_hi)) >= p.z.max(q.z) + MARGIN {
+                    return None;
+                }
+
+                // The curve crosses the line between these samples. Find where, by bisection.
+                let c = i / SAMPLES;
+                let (mut a, mut b) = (param(i % SAMPLES), param(i % SAMPLES + 1));
+                let positive_at_a = side(p) > 0.;
+                for _ in 0..64 {
+                    let m = 0.5 * (a + b);
+                    if m <= a || m >= b {
+                        break;
+                    }
+                    if (side(self.point_on(c, m)) > 0.) == positive_at_a {
+                        a = m;
+                    } else {
+                        b = m;
+                    }
+                }
+                let t = 0.5 * (a + b);
+                let pos = self.point_on(c, t);
+
+                let s = d.dot(&(pos - start).xy()) / d.norm_squared();
+                let z = start.z + s * (end.z - start.z);
+                if (0.0..1.0).contains(&s) && z < pos.z {
+                    // If the curve crosses from the negative side of the line to the positive, the line passes under it from left to right.
+                    Some((s, self.arc_on(c, t), !positive_at_a))
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        out.sort_by(|x, y| x.0.partial_cmp(&y.0).unwrap());
+        out
+    }
+
+    // The point of the knot nearest to `p`, to within the polyline's `MARGIN`.
+    pub fn nearest(&self, p: Vector3<f64>) -> Vector3<f64> {
+        let mut best = (f64::INFINITY, p);
+        for (c, (lo, hi)) in self.boxes.iter().enumerate() {
+            // The distance to the chunk's bounding box, looking down, is at most the distance to anything in it.
+            let outside = (lo - p.xy()).zip_map(&(p.xy() - hi), |a, b| a.max(b).max(0.));
+            if outside.norm() > best.0 + MARGIN {
+                continue;
+            }
+            for i in c * CHUNK..(c + 1) * CHUNK {
+                let [a, b] = self.segment(i);
+                let s = ((p - a).dot(&(b - a)) / (b - a).norm_squared()).clamp(0., 1.);
+                let q = a + (b - a) * s;
+                let distance = (p - q).norm();
+                if distance < best.0 {
+                    best = (distance, q);
+                }
+            }
+        }
+        best.1
+    }
+
+    fn segment(&self, i: usize) -> [Vector3<f64>; 2] {
+        [self.points[i], self.points[self.next(i)]]
+    }
+
+    // The sample after sample `i`, on the same component.
+    fn next(&self, i: usize) -> usize {
+        if i % SAMPLES == SAMPLES - 1 {
+            i + 1 - SAMPLES
+        } else {
+            i + 1
+        }
+    }
+
+    // The polyline segments which might cross the line from `a` to `b`.
+    fn candidates<'a>(
+        &'a self,
+        a: Vector2<f64>,
+        b: Vector2<f64>,
+    ) -> impl Iterator<Item = usize> + 'a {
+        let (lo, hi) = bounding_box(vec![a, b]);
+
+        // Long lines have big bounding boxes, so also skip boxes entirely on one side of the line.
+        let side = move |p: Vector2<f64>| (b - a).perp(&(p - a)) > 0.;
+
+        self.boxes
+            .iter()
+            .enumerate()
+            .filter(move |(_, (box_lo, box_hi))| {
+                let (box_lo, box_hi) = (box_lo.add_scalar(-MARGIN), box_hi.add_scalar(MARGIN));
+                let corners = [
+                    box_lo,
+                    Vector2::new(box_lo.x, box_hi.y),
+                    Vector2::new(box_hi.x, box_lo.y),
+                    box_hi,
+                ];
+                lo.x <= box_hi.x
+                    && box_lo.x <= hi.x
+                    && lo.y <= box_hi.y
+                    && box_lo.y <= hi.y
+                    && !corners.iter().all(|&p| side(p) == side(corners[0]))
+            })
+            .flat_map(|(c, _)| c * CHUNK..(c + 1) * CHUNK)
+    }
+
+    // Newton's method, to find parameters `t1`, `t2` near the given ones with the same projection.
+    fn refine_crossing(&self, t1: f64, t2: f64) -> (f64, f64) {
+        let (c1, c2) = (self.component(t1), self.component(t2));
+        let (mut t1, mut t2) = (t1 - c1 as f64 * TAU, t2 - c2 as f64 * TAU);
+        for _ in 0..16 {
+            let f = (self.point_on(c1, t1) - self.point_on(c2, t2)).xy();
+            let d1 = self.tangent_on(c1, t1).xy();
+            let d2 = -self.tangent_on(c2, t2).xy();
+
+            let det = d1.perp(&d2);
+            if det == 0.0 {
+                break;
+            }
+            t1 -= f.perp(&d2) / det;
+            t2 -= d1.perp(&f) / det;
+        }
+        (self.param_on(c1, t1), self.param_on(c2, t2))
+    }
+
+    // A Fox colouring of the diagram: a colour in `0..p` for each arc, not all the same,
+    // such that at every crossing, twice the colour of the over arc is the sum of the colours of the under arcs (mod p).
+    //
+    // Returns the smallest prime `p` for which one exists, with the colouring.
+    pub fn fox_colouring(&self) -> Option<(i32, Vec<i32>)> {
+        let n = self.arcs();
+        (3..100)
+            .filter(|&p| (2..p).all(|d| p % d != 0))
+            .find_map(|p| {
+                // One equation per crossing. We fix the colour of arc 0 to 0 and solve for the rest.
+                let rows: Vec<Vec<i32>> = self
+                    .crossings
+                    .iter()
+                    .map(|c| {
+                        let mut row = vec![0; n];
+                        row[c.over_arc] += 2;
+                        row[c.arc_in] -= 1;
+                        row[c.arc_out] -= 1;
+                        row[1..].iter().map(|&x: &i32| x.rem_euclid(p)).collect()
+                    })
+                    .collect();
+
+                let mut colours = vec![0];
+                colours.extend(kernel_vector(rows, n - 1, p)?);
+                Some((p, colours))
+            })
+    }
+}
+
+fn param(i: usize) -> f64 {
+    i as f64 * TAU / SAMPLES as f64
+}
+
+fn bounding_box(points: impl IntoIterator<Item = Vector2<f64>>) -> (Vector2<f64>, Vector2<f64>) {
+    let mut lo = Vector2::repeat(f64::INFINITY);
+    let mut hi = Vector2::repeat(-f64::INFINITY);
+    for p in points {
+        lo = lo.zip_map(&p, f64::min);
+        hi = hi.zip_map(&p, f64::max);
+    }
+    (lo, hi)
+}
+
+// Where do the segments `a` to `b` and `p` to `q` cross?
+// Returns the fractions of the way along each segment, each in `0..=1`, give or take rounding.
+//
+// A crossing at a vertex is found on both segments that share it. Better twice than not at all;
+// the duplicates are the same after refining, and are thrown away.
+fn intersect(
+    a: Vector2<f64>,
+    b: Vector2<f64>,
+    p: Vector2<f64>,
+    q: Vector2<f64>,
+) -> Option<(f64, f64)> {
+    let ab = b - a;
+    let pq = q - p;
+    let ap = p - a;
+
+    let denom = ab.perp(&pq);
+    if denom == 0.0 {
+        return None;
+    }
+    let s = ap.perp(&pq) / denom;
+    let u = ap.perp(&ab) / denom;
+
+    const EPSILON: f64 = 1e-9;
+    let range = -EPSILON..=1. + EPSILON;
+    if range.contains(&s) && range.contains(&u) {
+        Some((s, u))
+    } else {
+        None
+    }
+}
+
+// A nonzero solution of `rows * x = 0` (mod p), with `x` of length `n`, if there is one.
+// `p` must be prime.
+fn kernel_vector(mut rows: Vec<Vec<i32>>, n: usize, p: i32) -> Option<Vec<i32>> {
+    let inverse = |a: i32| (1..p).find(|&b| a * b % p == 1).unwrap();
+
+    // Gaussian elimination, recording which column each pivot is in.
+    let mut pivots: Vec<usize> = Vec::new();
+    for col in 0..n {
+        let r = pivots.len();
+        let pivot = match (r..rows.len()).find(|&i| rows[i][col] != 0) {
+            Some(pivot) => pivot,
+            None => continue,
+        };
+        rows.swap(r, pivot);
+
+        let inv = inverse(rows[r][col]);
+        for x in rows[r].iter_mut() {
+            *x = *x * inv % p;
+        }
+        let pivot_row = rows[r].clone();
+        for (i, row) in rows.iter_mut().enumerate() {
+            if i != r && row[col] != 0 {
+                let factor = row[col];
+                for (x, y) in row.iter_mut().zip(&pivot_row) {
+                    *x = (*x - factor * y).rem_euclid(p);
+                }
+            }
+        }
+        pivots.push(col);
+    }
+
+    // Set the first free variable to 1, and the others to 0.
+    let free = (0..n).find(|col| !pivots.contains(col))?;
+    let mut x = vec![0; n];
+    x[free] = 1;
+    for (r, &col) in pivots.iter().enumerate() {
+        x[col] = (-rows[r][free]).rem_euclid(p);
+    }
+    Some(x)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Knot;
+
+    #[test]
+    fn crossing_numbers() {
+        assert_eq!(Knot::trefoil().crossings().len(), 3);
+        assert_eq!(Knot::torus(2, 3).crossings().len(), 3);
+        assert_eq!(Knot::torus(2, 5).crossings().len(), 5);
+    }
+
+    #[test]
+    fn links() {
+        for (link, components, crossings) in [
+            (Knot::hopf(), 2, 2),
+            (Knot::borromean(), 3, 6),
+            (Knot::whitehead(), 2, 5),
+        ] {
+            assert_eq!(link.components(), components);
+            assert_eq!(link.crossings().len(), crossings);
+            // Every component passes under something, so each undercrossing starts an arc.
+            assert_eq!(link.arcs(), crossings);
+        }
+    }
+
+    #[test]
+    fn nearest() {
+        let knot = Knot::whitehead();
+        for &p in &[
+            nalgebra::Vector3::new(0., 0., 0.),
+            nalgebra::Vector3::new(1., -0.5, 0.3),
+            nalgebra::Vector3::new(-2., 2., -1.),
+            nalgebra::Vector3::new(5., 1., 2.),
+        ] {
+            let fine = (0..knot.components())
+                .flat_map(|c| (0..100_000).map(move |i| (c, i)))
+                .map(|(c, i)| {
+                    let t =
+                        c as f64 * std::f64::consts::TAU + i as f64 * 1e-5 * std::f64::consts::TAU;
+                    (knot.point(t) - p).norm()
+                })
+                .fold(f64::INFINITY, f64::min);
+            assert!(((knot.nearest(p) - p).norm() - fine).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn dihedral_portals() {
+        // These check the crossing relations as they're built.
+        let figure_eight = crate::portal::Portal::dihedral(Knot::figure_eight());
+        assert_eq!(figure_eight.worlds(), 10);
+        let cinquefoil = crate::portal::Portal::dihedral(Knot::torus(2, 5));
+        assert_eq!(cinquefoil.worlds(), 10);
+    }
+
+    #[test]
+    fn fox_colourings() {
+        assert_eq!(Knot::trefoil().fox_colouring().unwrap().0, 3);
+        assert_eq!(Knot::figure_eight().fox_colouring().unwrap().0, 5);
+        assert_eq!(Knot::torus(2, 5).fox_colouring().unwrap().0, 5);
+    }
+
+    #[test]
+    fn polyline_sizes() {
+        let whitehead = Knot::whitehead();
+        let polyline = whitehead.polyline(super::SHADER_VERTICES).unwrap();
+        assert!(polyline.len() <= super::SHADER_VERTICES);
+        assert_eq!(polyline.iter().filter(|(_, arc)| arc.is_none()).count(), 2);
+        // Too few vertices for the undercrossings, and then too few for anything else.
+        assert!(whitehead.polyline(4).is_none());
+        assert!(whitehead.polyline(whitehead.arcs() + 6).is_none());
+    }
+}
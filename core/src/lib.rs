@@ -0,0 +1,181 @@
+#![forbid(unsafe_code)]
+
+pub mod expr;
+pub mod gamepad;
+pub mod knot;
+pub mod minimap;
+pub mod model;
+pub mod modeling;
+pub mod physics;
+pub mod replay;
+pub mod scene;
+pub mod settings;
+pub mod touch;
+
+/// The shader's version of `Portal::travel` works from the polyline in `Knot::polyline`, rather than the curve itself.
+/// This version decides where the balls and the player end up, and so has to be exact.
+pub mod portal;
+
+// How close the camera can get to things.
+pub const PLAYER_RADIUS: f32 = 0.1;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Mode {
+    Fly,
+    // Falling under gravity, with `pos` at eye height above whatever the player stands on.
+    Walk,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Player {
+    pub pos: nalgebra::Vector3<f32>,
+    pub theta: f32,
+    pub phi: f32,
+    pub world: i32,
+
+    pub mode: Mode,
+    // Only used while walking.
+    pub vertical_speed: f32,
+    pub standing: bool,
+}
+
+impl Player {
+    pub fn new() -> Self {
+        Self {
+            pos: nalgebra::Vector3::new(5.0, 0.0, 0.0),
+            theta: 0.,
+            phi: 0.,
+            world: 0,
+
+            mode: Mode::Fly,
+            vertical_speed: 0.,
+            standing: false,
+        }
+    }
+
+    // Player space -> World space
+    pub fn isometry(&self) -> nalgebra::Isometry3<f32> {
+        let (st, ct) = self.theta.sin_cos();
+        let (sp, cp) = self.phi.sin_cos();
+
+        nalgebra::Isometry3::face_towards(
+            &nalgebra::Point3 { coords: self.pos },
+            &nalgebra::Point3 {
+                coords: self.pos + nalgebra::Vector3::new(ct * cp, -st * cp, -sp),
+            },
+            &nalgebra::Vector3::z(),
+        )
+    }
+
+    // Move by `v`, sliding along the ground, the portal's frame and the edge of the world rather than passing through them.
+    // Returns the arcs passed under on the way.
+    pub fn travel(
+        &mut self,
+        portal: &portal::Portal,
+        v: nalgebra::Vector3<f32>,
+    ) -> Vec<portal::Crossing> {
+        let mut crossed = Vec::new();
+        // In steps shorter than the player's radius, so as not to skip through the frame.
+        // However fast the player goes, there are no more steps than it takes to cross the whole world.
+        let max_steps = (2. * modeling::WORLD_RADIUS / (0.5 * PLAYER_RADIUS)).ceil();
+        let steps = (v.norm() / (0.5 * PLAYER_RADIUS))
+            .ceil()
+            .clamp(1., max_steps);
+        for _ in 0..steps as usize {
+            let mut newpos = physics::push_out(portal, self.pos + v / steps, PLAYER_RADIUS, |_| {});
+            if newpos.norm() > modeling::WORLD_RADIUS {
+                newpos *= modeling::WORLD_RADIUS / newpos.norm();
+            }
+            let crossings = portal.crossings(self.world, self.pos, newpos);
+            if let Some(last) = crossings.last() {
+                self.world = last.to;
+            }
+            crossed.extend(crossings);
+            self.pos = newpos;
+        }
+        crossed
+    }
+}
+
+impl Default for Player {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Where a ball is at each moment.
+pub type Path = Box<dyn Fn(f32) -> nalgebra::Vector3<f32> + Send + Sync>;
+
+pub enum Motion {
+    // Following a path: at time `t`, the ball is at `path(t)`.
+    Path(Path),
+    // Moving freely: falling, and bouncing off the ground, the portal's frame and other balls. See `physics`.
+    Free,
+}
+
+pub struct Ball {
+    // Unique among the model's balls: the scene's are numbered from 0, in order, and balls added later get the next numbers.
+    pub id: u32,
+    pub color: [f32; 4],
+    pub motion: Motion,
+    pub pos: nalgebra::Vector3<f32>,
+    pub velocity: nalgebra::Vector3<f32>,
+    pub t: f32,
+    pub world: i32,
+}
+
+impl Ball {
+    pub fn new(
+        color: [f32; 4],
+        world: i32,
+        path: impl Fn(f32) -> nalgebra::Vector3<f32> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            id: 0,
+            color,
+            pos: path(0.),
+            motion: Motion::Path(Box::new(path)),
+            velocity: nalgebra::Vector3::zeros(),
+            t: 0.,
+            world,
+        }
+    }
+
+    pub fn free(
+        color: [f32; 4],
+        world: i32,
+        pos: nalgebra::Vector3<f32>,
+        velocity: nalgebra::Vector3<f32>,
+    ) -> Self {
+        Self {
+            id: 0,
+            color,
+            motion: Motion::Free,
+            pos,
+            velocity,
+            t: 0.,
+            world,
+        }
+    }
+
+    pub fn travel(&mut self, portal: &portal::Portal, dt: f32) {
+        let t = self.t + dt;
+        let pos = match &self.motion {
+            Motion::Path(path) => {
+                let pos = path(t);
+                if dt > 0. {
+                    self.velocity = (pos - self.pos) / dt;
+                }
+                pos
+            }
+            Motion::Free => physics::fall(portal, self.pos, &mut self.velocity, dt),
+        };
+        portal.travel(&mut self.world, self.pos, pos);
+        self.t = t;
+        self.pos = pos;
+    }
+
+    pub fn geometry(&self, worlds: i32) -> impl IntoIterator<Item = modeling::Triangle> {
+        modeling::ball(self.pos, self.world, self.color, worlds)
+    }
+}
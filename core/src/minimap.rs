@@ -0,0 +1,219 @@
+// A map of the portal, for the front-end to draw over the view, so that it's clear what's going on.
+//
+// It has two panels. One is the knot diagram, the frame's shadow on the ground, with a gap in each strand where it passes under another,
+// on which the front-end plots the player and the balls. The other is the graph of the worlds, with an edge for each pair of worlds
+// that passing under an arc swaps between, on which the front-end highlights the player's world and the arcs they passed under recently.
+//
+// Each panel is a unit square, with y pointing down the screen, as the 2D canvas has it.
+
+use crate::portal::Portal;
+use nalgebra::{Vector2, Vector3};
+
+// How many vertices of the knot's polyline to draw.
+const VERTICES: usize = 512;
+// The space around the knot diagram, and around the graph, as a fraction of the panel.
+const MARGIN: f32 = 0.1;
+// How much of each strand to leave out either side of an undercrossing, as a fraction of the panel.
+const GAP: f32 = 0.025;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Edge {
+    pub arc: usize,
+    // `from < to`. Passing under `arc` one way or the other goes between them.
+    pub from: i32,
+    pub to: i32,
+}
+
+pub struct Minimap {
+    /// Each arc of the knot diagram, as a polyline.
+    pub arcs: Vec<Vec<[f32; 2]>>,
+    /// Where to put each world in the graph.
+    pub worlds: Vec<[f32; 2]>,
+    pub edges: Vec<Edge>,
+
+    // The knot diagram is the xy-plane, scaled by `scale` about `centre`.
+    centre: Vector2<f32>,
+    scale: f32,
+}
+
+impl Minimap {
+    pub fn new(portal: &Portal) -> Self {
+        let knot = &portal.knot;
+        let polyline: Vec<(Vector2<f32>, Option<usize>)> = knot
+            .polyline(VERTICES)
+            // This has room for more vertices than the shader's polyline, which the knot has to fit.
+            .expect("the knot has too many crossings for the minimap")
+            .into_iter()
+            .map(|(p, arc)| (p.xy().map(|x| x as f32), arc))
+            .collect();
+
+        let (min, max) = polyline.iter().fold(
+            (
+                Vector2::repeat(f32::INFINITY),
+                Vector2::repeat(f32::NEG_INFINITY),
+            ),
+            |(min, max), (p, _)| {
+                (
+                    Vector2::new(min.x.min(p.x), min.y.min(p.y)),
+                    Vector2::new(max.x.max(p.x), max.y.max(p.y)),
+                )
+            },
+        );
+        let centre = (min + max) / 2.;
+        let scale = (1. - 2. * MARGIN) / (max - min).max();
+
+        // Each component's polyline starts part way along an arc, so that arc comes in two pieces: the start, and the end.
+        let mut pieces: Vec<Vec<Vec<Vector2<f32>>>> = vec![Vec::new(); knot.arcs()];
+        let mut previous = None;
+        for window in polyline.windows(2) {
+            if let (p, Some(arc)) = window[0] {
+                if previous != Some(arc) {
+                    pieces[arc].push(vec![p]);
+                }
+                pieces[arc].last_mut().unwrap().push(window[1].0);
+            }
+            previous = window[0].1;
+        }
+
+        let arcs = pieces
+            .into_iter()
+            .enumerate()
+            .map(|(k, mut pieces)| {
+                let mut arc = pieces.pop().unwrap_or_default();
+                for piece in pieces {
+                    arc.extend(piece.into_iter().skip(1));
+                }
+                let mut arc: Vec<Vector2<f32>> =
+                    arc.into_iter().map(|p| (p - centre) * scale).collect();
+
+                // An arc starts and ends at undercrossings, unless its component never passes under anything.
+                if knot.crossings().iter().any(|c| c.arc_out == k) {
+                    trim(&mut arc, GAP);
+                    arc.reverse();
+                    trim(&mut arc, GAP);
+                    arc.reverse();
+                }
+                arc.into_iter().map(|p| [0.5 + p.x, 0.5 - p.y]).collect()
+            })
+            .collect();
+
+        let n = portal.worlds();
+        let worlds = (0..n)
+            .map(|w| {
+                // Around a circle, starting on the left.
+                let angle = std::f32::consts::PI * (1. + 2. * w as f32 / n as f32);
+                let radius = 0.5 - MARGIN;
+                [0.5 + radius * angle.cos(), 0.5 - radius * angle.sin()]
+            })
+            .collect();
+
+        let mut edges: Vec<Edge> = (0..knot.arcs())
+            .flat_map(|arc| {
+                portal
+                    .permutation(arc)
+                    .iter()
+                    .enumerate()
+                    .filter(|&(w, &v)| w as i32 != v)
+                    .map(move |(w, &v)| Edge {
+                        arc,
+                        from: v.min(w as i32),
+                        to: v.max(w as i32),
+                    })
+            })
+            .collect();
+        edges.sort();
+        edges.dedup();
+
+        Self {
+            arcs,
+            worlds,
+            edges,
+            centre,
+            scale,
+        }
+    }
+
+    /// Where a point goes on the knot diagram. Points beyond the edge of the panel are kept just inside it.
+    pub fn place(&self, pos: Vector3<f32>) -> [f32; 2] {
+        let p = (pos.xy() - self.centre) * self.scale;
+        [(0.5 + p.x).clamp(0., 1.), (0.5 - p.y).clamp(0., 1.)]
+    }
+
+    /// Which way the player is facing on the knot diagram, as a unit vector.
+    pub fn heading(theta: f32) -> [f32; 2] {
+        // Forward is -x, turned by `theta` clockwise, looking down.
+        [-theta.cos(), -theta.sin()]
+    }
+}
+
+// Remove the first `length` of a polyline.
+fn trim(polyline: &mut Vec<Vector2<f32>>, mut length: f32) {
+    while polyline.len() >= 2 {
+        let segment = polyline[1] - polyline[0];
+        let l = segment.norm();
+        if l > length {
+            polyline[0] += segment * (length / l);
+            return;
+        }
+        length -= l;
+        polyline.remove(0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trefoil() {
+        let portal = Portal::trefoil();
+        let minimap = Minimap::new(&portal);
+
+        assert_eq!(minimap.arcs.len(), 3);
+        for arc in &minimap.arcs {
+            assert!(arc.len() > 10);
+            for p in arc {
+                assert!(p
+                    .iter()
+                    .all(|&x| (MARGIN - 1e-3..=1. - MARGIN + 1e-3).contains(&x)));
+            }
+            for pair in arc.windows(2) {
+                let d = [pair[1][0] - pair[0][0], pair[1][1] - pair[0][1]];
+                assert!((d[0] * d[0] + d[1] * d[1]).sqrt() < 0.1);
+            }
+        }
+
+        // Each arc's ends are left out where it passes under the next, so no two arcs meet.
+        for (i, a) in minimap.arcs.iter().enumerate() {
+            for b in &minimap.arcs[i + 1..] {
+                for p in &[a[0], a[a.len() - 1]] {
+                    for q in &[b[0], b[b.len() - 1]] {
+                        let d = [p[0] - q[0], p[1] - q[1]];
+                        assert!((d[0] * d[0] + d[1] * d[1]).sqrt() > GAP);
+                    }
+                }
+            }
+        }
+
+        // Each arc is a reflection of the hexagon, which swaps three pairs of worlds.
+        assert_eq!(minimap.worlds.len(), 6);
+        assert_eq!(minimap.edges.len(), 9);
+        for edge in &minimap.edges {
+            assert_eq!(portal.transition(edge.arc, true, edge.from), edge.to);
+        }
+    }
+
+    #[test]
+    fn placing_the_player() {
+        let minimap = Minimap::new(&Portal::trefoil());
+        let centre = minimap.place(Vector3::new(minimap.centre.x, minimap.centre.y, 5.));
+        assert!((centre[0] - 0.5).abs() < 1e-6 && (centre[1] - 0.5).abs() < 1e-6);
+
+        // Up the page is +y.
+        let [_, y] = minimap.place(Vector3::new(minimap.centre.x, minimap.centre.y + 1., 0.));
+        assert!(y < 0.5);
+
+        // Far away, at the edge.
+        assert_eq!(minimap.place(Vector3::new(100., 0., 0.))[0], 1.);
+    }
+}
@@ -0,0 +1,868 @@
+use crate::modeling::{self, Triangle, Uniforms, GROUND_HEIGHT};
+use crate::portal::{Crossing, Portal};
+use crate::replay::{Analog, End, Event, Input, Replay};
+use crate::scene::{self, Scene};
+use crate::settings::Settings;
+use crate::{Ball, Mode, Player};
+use std::collections::{HashSet, VecDeque};
+
+// The game always moves forward in ticks of this many seconds, however often it's drawn,
+// so that the same input always gives exactly the same result.
+pub const TICK: f32 = 1. / 60.;
+
+// After a longer stall, such as while the tab is hidden, the game skips time rather than catching up all at once.
+const MAX_FRAME: f32 = 0.25;
+
+// How many times faster sprinting is than walking.
+const SPRINT: f32 = 2.4;
+// In units per second.
+const JUMP_SPEED: f32 = 1.5;
+
+// How many of the arcs the player passed under to remember, for debugging.
+const RECENT_CROSSINGS: usize = 8;
+
+// How many world changes to keep for a front-end that never asks for them.
+const UNCLAIMED_WORLD_CHANGES: usize = 256;
+
+// Low enough to walk under the trefoil's arcs.
+pub const EYE_HEIGHT: f32 = 0.5;
+
+/// Everything that happens in the game, independent of the platform it's running on.
+/// The front-ends feed it input and time, and draw what it describes.
+pub struct Model {
+    pub portal: Portal,
+    pub player: Player,
+    pub balls: Vec<Ball>,
+
+    /// The keys being held, as `KeyboardEvent.code` names them.
+    pub keys: HashSet<String>,
+    /// Where the gamepad's sticks and triggers are.
+    pub analog: Analog,
+    pub settings: Settings,
+
+    static_geometry: Vec<Triangle>,
+
+    ticks: u64,
+    // Time that has passed but not yet been simulated, less than one tick.
+    unsimulated: f32,
+    // The state before the last tick. The game is drawn between it and the current state, `unsimulated` of the way along.
+    previous: Snapshot,
+    // The arcs the player passed under most recently, oldest first, with the tick when they did.
+    crossings: VecDeque<(u64, Crossing)>,
+    // Since the front-end last took them.
+    world_changes: VecDeque<WorldChange>,
+    // The id for the next ball added.
+    next_ball: u32,
+    sky: Vec<[f32; 4]>,
+
+    // The scene's JSON, and all the input so far, for `replay`.
+    scene: serde_json::Value,
+    inputs: Vec<Event>,
+    // Input from a replay, waiting for its tick.
+    queued: VecDeque<Event>,
+    // The tick the replay being played ends at, which can be well after its last input.
+    replay_end: u64,
+}
+
+/// Everything about the game that changes as it runs, except the keys being held.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Snapshot {
+    pub ticks: u64,
+    pub player: Player,
+    pub balls: Vec<BallState>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct BallState {
+    pub pos: nalgebra::Vector3<f32>,
+    pub velocity: nalgebra::Vector3<f32>,
+    pub t: f32,
+    pub world: i32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Traveller {
+    Player,
+    // A ball, by its id.
+    Ball(u32),
+}
+
+/// The player or a ball went from one world to another.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WorldChange {
+    pub tick: u64,
+    pub who: Traveller,
+    pub from: i32,
+    pub to: i32,
+}
+
+impl Model {
+    /// The default scene, `scenes/trefoil.json`.
+    pub fn new() -> Self {
+        Self::from_json(scene::DEFAULT).unwrap()
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, scene::Error> {
+        Self::from_scene(&Scene::from_json(json)?, serde_json::from_str(json)?)
+    }
+
+    pub(crate) fn from_scene(scene: &Scene, json: serde_json::Value) -> Result<Self, scene::Error> {
+        let portal = scene.portal()?;
+        let mut balls = scene.balls(&portal)?;
+        for (id, ball) in balls.iter_mut().enumerate() {
+            ball.id = id as u32;
+        }
+        let mut out = Self {
+            static_geometry: scene.static_geometry(&portal)?,
+            sky: scene.sky(&portal)?,
+            next_ball: balls.len() as u32,
+            balls,
+            portal,
+            player: Player::new(),
+            keys: HashSet::new(),
+            analog: Analog::default(),
+            settings: Settings::default(),
+
+            ticks: 0,
+            unsimulated: 0.,
+            previous: Snapshot {
+                ticks: 0,
+                player: Player::new(),
+                balls: Vec::new(),
+            },
+
+            scene: json,
+            inputs: Vec::new(),
+            queued: VecDeque::new(),
+            replay_end: 0,
+            crossings: VecDeque::new(),
+            world_changes: VecDeque::new(),
+        };
+        out.previous = out.snapshot();
+        Ok(out)
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            ticks: self.ticks,
+            player: self.player.clone(),
+            balls: self
+                .balls
+                .iter()
+                .map(|ball| BallState {
+                    pos: ball.pos,
+                    velocity: ball.velocity,
+                    t: ball.t,
+                    world: ball.world,
+                })
+                .collect(),
+        }
+    }
+
+    /// How many ticks the game has run for.
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    /// The arcs the player passed under most recently, oldest first, with the tick when they did.
+    pub fn recent_crossings(&self) -> impl Iterator<Item = &(u64, Crossing)> {
+        self.crossings.iter()
+    }
+
+    /// Which worlds the player and the balls went between since this was last called, oldest first.
+    pub fn take_world_changes(&mut self) -> Vec<WorldChange> {
+        self.world_changes.drain(..).collect()
+    }
+
+    /// The colour of each world's sky.
+    pub fn sky(&self) -> &[[f32; 4]] {
+        &self.sky
+    }
+
+    /// The geometry that never changes: the knot, the sky, the ground and the scene's meshes.
+    pub fn static_geometry(&self) -> impl Iterator<Item = Triangle> + '_ {
+        self.static_geometry.iter().cloned()
+    }
+
+    /// The geometry that changes every frame: the balls.
+    pub fn dynamic_geometry(&self) -> Vec<Triangle> {
+        self.balls
+            .iter()
+            .zip(&self.previous.balls)
+            .flat_map(|(ball, previous)| {
+                let (pos, world) = self.between(previous.pos, previous.world, ball.pos, ball.world);
+                modeling::ball(pos, world, ball.color, self.portal.worlds())
+            })
+            .collect()
+    }
+
+    pub fn uniforms(&self) -> Uniforms {
+        // Looking around isn't simulated in ticks, so the view turns as soon as the mouse moves.
+        let mut player = self.player.clone();
+        let previous = &self.previous.player;
+        let (pos, world) = self.between(previous.pos, previous.world, player.pos, player.world);
+        player.pos = pos;
+        player.world = world;
+
+        Uniforms {
+            light_dir: nalgebra::Vector3::new(1.0, 1.0, 1.0).normalize(),
+            player_isometry: player.isometry(),
+            player_world: player.world,
+            fov: self.settings.fov.to_radians(),
+        }
+    }
+
+    // Where something that moved from `a` to `b` during the last tick is drawn, and in which world.
+    fn between(
+        &self,
+        a: nalgebra::Vector3<f32>,
+        a_world: i32,
+        b: nalgebra::Vector3<f32>,
+        b_world: i32,
+    ) -> (nalgebra::Vector3<f32>, i32) {
+        let pos = a.lerp(&b, self.unsimulated / TICK);
+        if a_world == b_world {
+            return (pos, b_world);
+        }
+        // It went through the portal, and the point drawn might be on either side of it.
+        let mut world = a_world;
+        self.portal.travel(&mut world, a, pos);
+        (pos, world)
+    }
+
+    pub fn key_down(&mut self, key: &str) {
+        self.input(Input::KeyDown(key.to_string()));
+    }
+
+    pub fn key_up(&mut self, key: &str) {
+        self.input(Input::KeyUp(key.to_string()));
+    }
+
+    /// Let go of every key held, for when the window loses the keyboard and won't hear them let go of.
+    pub fn release_keys(&mut self) {
+        let mut keys: Vec<String> = self.keys.iter().cloned().collect();
+        // In a fixed order, for the replay.
+        keys.sort();
+        for key in keys {
+            self.key_up(&key);
+        }
+    }
+
+    /// Turn the player's head, by a mouse movement measured in pixels.
+    pub fn look(&mut self, movement: [f32; 2]) {
+        self.input(Input::Look(movement));
+    }
+
+    /// Move the player to `position`, facing `theta` and `phi`, in `world`.
+    pub fn set_player_pose(
+        &mut self,
+        position: [f32; 3],
+        theta: f32,
+        phi: f32,
+        world: i32,
+    ) -> Result<(), String> {
+        self.check_world(world)?;
+        self.input(Input::SetPose {
+            position,
+            theta,
+            phi,
+            world,
+        });
+        Ok(())
+    }
+
+    /// Add a ball that moves freely from `position`, returning its id.
+    pub fn add_ball(
+        &mut self,
+        color: [f32; 4],
+        world: i32,
+        position: [f32; 3],
+        velocity: [f32; 3],
+    ) -> Result<u32, String> {
+        self.check_world(world)?;
+        let id = self.next_ball;
+        self.input(Input::AddBall {
+            color,
+            world,
+            position,
+            velocity,
+        });
+        Ok(id)
+    }
+
+    pub fn remove_ball(&mut self, id: u32) -> Result<(), String> {
+        if !self.balls.iter().any(|ball| ball.id == id) {
+            return Err(format!("there is no ball {}", id));
+        }
+        self.input(Input::RemoveBall(id));
+        Ok(())
+    }
+
+    fn check_world(&self, world: i32) -> Result<(), String> {
+        if (0..self.portal.worlds()).contains(&world) {
+            Ok(())
+        } else {
+            Err(format!(
+                "there is no world {}: there are only {} worlds",
+                world,
+                self.portal.worlds()
+            ))
+        }
+    }
+
+    pub fn input(&mut self, input: Input) {
+        match &input {
+            Input::KeyDown(key) => {
+                // Holding a key down repeats it, so only toggle when it's first pressed.
+                if self.keys.insert(key.clone()) && self.settings.bindings.toggle_mode.contains(key)
+                {
+                    self.toggle_mode();
+                }
+            }
+            Input::KeyUp(key) => {
+                self.keys.remove(key);
+            }
+            Input::Look([x, y]) => {
+                let y = if self.settings.invert_y { -y } else { *y };
+                self.turn([
+                    x * self.settings.sensitivity,
+                    -y * self.settings.sensitivity,
+                ]);
+            }
+            Input::Analog(analog) => self.analog = *analog,
+            &Input::SetPose {
+                position,
+                theta,
+                phi,
+                world,
+            } => {
+                let from = self.player.world;
+                self.player.pos = position.into();
+                self.player.theta = 0.;
+                self.player.phi = 0.;
+                self.turn([theta, phi]);
+                self.player.world = world;
+                self.player.vertical_speed = 0.;
+                self.player.standing = false;
+                // Jump straight there, rather than being drawn on the way.
+                self.previous.player = self.player.clone();
+                if world != from {
+                    self.world_changed(Traveller::Player, from, world);
+                }
+            }
+            &Input::AddBall {
+                color,
+                world,
+                position,
+                velocity,
+            } => {
+                let mut ball = Ball::free(color, world, position.into(), velocity.into());
+                ball.id = self.next_ball;
+                self.next_ball += 1;
+                self.previous.balls.push(BallState {
+                    pos: ball.pos,
+                    velocity: ball.velocity,
+                    t: ball.t,
+                    world,
+                });
+                self.balls.push(ball);
+            }
+            &Input::RemoveBall(id) => {
+                if let Some(b) = self.balls.iter().position(|ball| ball.id == id) {
+                    self.balls.remove(b);
+                    self.previous.balls.remove(b);
+                }
+            }
+        }
+        self.inputs.push(Event {
+            tick: self.ticks,
+            input,
+        });
+    }
+
+    // Turn right and up, by angles in radians.
+    fn turn(&mut self, [right, up]: [f32; 2]) {
+        self.player.theta += right;
+        self.player.phi += up;
+
+        self.player.phi = self.player.phi.clamp(
+            -std::f32::consts::FRAC_PI_2 + 0.001,
+            std::f32::consts::FRAC_PI_2 - 0.001,
+        );
+    }
+
+    fn toggle_mode(&mut self) {
+        self.player.mode = match self.player.mode {
+            Mode::Fly => Mode::Walk,
+            Mode::Walk => Mode::Fly,
+        };
+        self.player.vertical_speed = 0.;
+        self.player.standing = false;
+    }
+
+    /// Everything that has happened so far, to play back later.
+    pub fn replay(&self) -> Replay {
+        Replay {
+            scene: self.scene.clone(),
+            settings: self.settings.clone(),
+            inputs: self.inputs.clone(),
+            ticks: self.ticks,
+            end: End::of(self),
+        }
+    }
+
+    /// Whether a replay is still playing. If so, the front-ends should ignore the player's own input.
+    pub fn replaying(&self) -> bool {
+        self.ticks < self.replay_end || !self.queued.is_empty()
+    }
+
+    pub(crate) fn queue(&mut self, inputs: impl IntoIterator<Item = Event>, end: u64) {
+        self.queued.extend(inputs);
+        self.replay_end = end;
+    }
+
+    // Apply the queued input that arrived at this tick, or before.
+    pub(crate) fn flush(&mut self) {
+        while let Some(event) = self.queued.front() {
+            if event.tick > self.ticks {
+                break;
+            }
+            let event = self.queued.pop_front().unwrap();
+            self.input(event.input);
+        }
+    }
+
+    /// Advance the game by `dt` seconds: as many whole ticks as have passed, keeping the rest for next time.
+    pub fn update(&mut self, dt: f32) {
+        self.unsimulated += dt.min(MAX_FRAME);
+        while self.unsimulated >= TICK {
+            self.unsimulated -= TICK;
+            self.tick();
+        }
+    }
+
+    /// Advance the game by exactly one tick.
+    pub fn tick(&mut self) {
+        self.flush();
+        self.previous = self.snapshot();
+        let [right, up] = self.analog.look;
+        if right != 0. || up != 0. {
+            self.turn([right * TICK, up * TICK]);
+        }
+        self.move_player(TICK);
+        self.move_balls(TICK);
+        self.ticks += 1;
+    }
+
+    fn move_player(&mut self, dt: f32) {
+        let bindings = &self.settings.bindings;
+        let held = |keys: &[String]| keys.iter().any(|key| self.keys.contains(key));
+        let speed = self.settings.speed;
+
+        let mut v: nalgebra::Vector3<f32> = nalgebra::Vector3::zeros();
+        if held(&bindings.forward) {
+            v -= nalgebra::Vector3::x();
+        }
+        if held(&bindings.back) {
+            v += nalgebra::Vector3::x();
+        }
+        if held(&bindings.left) {
+            v -= nalgebra::Vector3::y();
+        }
+        if held(&bindings.right) {
+            v += nalgebra::Vector3::y();
+        }
+
+        let [right, forward] = self.analog.movement;
+        v.x = (v.x - forward).clamp(-1., 1.);
+        v.y = (v.y + right).clamp(-1., 1.);
+        // When walking, half-pressed triggers count as pressed.
+        let up = held(&bindings.up) || self.analog.vertical > 0.5;
+        let down = held(&bindings.down) || self.analog.vertical < -0.5;
+
+        match self.player.mode {
+            Mode::Fly => {
+                if held(&bindings.up) {
+                    v += nalgebra::Vector3::z();
+                }
+                if held(&bindings.down) {
+                    v -= nalgebra::Vector3::z();
+                }
+                v.z = (v.z + self.analog.vertical).clamp(-1., 1.);
+                v *= speed * dt;
+            }
+            Mode::Walk => {
+                if down {
+                    v *= SPRINT;
+                }
+                v *= speed * dt;
+
+                if up && self.player.standing {
+                    self.player.vertical_speed = JUMP_SPEED;
+                }
+                self.player.vertical_speed -= crate::physics::GRAVITY * dt;
+                v.z = self.player.vertical_speed * dt;
+            }
+        }
+
+        v = nalgebra::UnitQuaternion::new(-self.player.theta * nalgebra::Vector3::z()) * v;
+        let before = self.player.pos;
+        let mut crossed = self.player.travel(&self.portal, v);
+
+        if self.player.mode == Mode::Walk {
+            // Landing on something, maybe the top of the frame, stops the fall.
+            self.player.standing = v.z < 0. && self.player.pos.z - before.z > v.z * 0.5;
+
+            let lowest = GROUND_HEIGHT + EYE_HEIGHT;
+            if self.player.pos.z < lowest {
+                let up = nalgebra::Vector3::z() * (lowest - self.player.pos.z);
+                crossed.extend(self.player.travel(&self.portal, up));
+                self.player.standing = true;
+            }
+
+            if self.player.standing {
+                self.player.vertical_speed = 0.;
+            }
+        }
+
+        for crossing in crossed {
+            if self.crossings.len() == RECENT_CROSSINGS {
+                self.crossings.pop_front();
+            }
+            self.crossings.push_back((self.ticks, crossing));
+            self.world_changed(Traveller::Player, crossing.from, crossing.to);
+        }
+    }
+
+    fn move_balls(&mut self, dt: f32) {
+        let before: Vec<i32> = self.balls.iter().map(|ball| ball.world).collect();
+        crate::physics::step(&self.portal, &mut self.balls, dt);
+        let changes: Vec<_> = self
+            .balls
+            .iter()
+            .zip(before)
+            .filter(|(ball, from)| ball.world != *from)
+            .map(|(ball, from)| (Traveller::Ball(ball.id), from, ball.world))
+            .collect();
+        for (who, from, to) in changes {
+            self.world_changed(who, from, to);
+        }
+    }
+
+    fn world_changed(&mut self, who: Traveller, from: i32, to: i32) {
+        if self.world_changes.len() == UNCLAIMED_WORLD_CHANGES {
+            self.world_changes.pop_front();
+        }
+        self.world_changes.push_back(WorldChange {
+            tick: self.ticks,
+            who,
+            from,
+            to,
+        });
+    }
+}
+
+impl Default for Model {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(model: &mut Model, seconds: f32) {
+        for _ in 0..(seconds / TICK).round() as usize {
+            model.tick();
+        }
+    }
+
+    #[test]
+    fn walking() {
+        let mut model = Model::new();
+        let start = model.player.pos;
+
+        model.key_down("KeyW");
+        run(&mut model, 1.);
+        assert!((model.player.pos - (start - nalgebra::Vector3::x() * 0.5)).norm() < 1e-5);
+
+        model.key_up("KeyW");
+        run(&mut model, 1.);
+        assert!((model.player.pos - (start - nalgebra::Vector3::x() * 0.5)).norm() < 1e-5);
+    }
+
+    #[test]
+    fn solid_frame_and_ground() {
+        let model = Model::new();
+        let mut player = Player::new();
+
+        // Sliding along the ground.
+        player.travel(&model.portal, nalgebra::Vector3::new(1., 0., -10.));
+        assert!((player.pos - nalgebra::Vector3::new(6., 0., -1.9)).norm() < 1e-4);
+
+        // Falling onto the top of the frame.
+        let top: nalgebra::Vector3<f32> =
+            nalgebra::convert(model.portal.knot.point(std::f64::consts::PI / 6.));
+        player.pos = top + nalgebra::Vector3::z();
+        player.travel(&model.portal, nalgebra::Vector3::new(0., 0., -2.));
+        let p = nalgebra::convert(player.pos);
+        let distance = (model.portal.knot.nearest(p) - p).norm();
+        assert!((distance - 0.3).abs() < 1e-3);
+        assert!(player.pos.z > top.z);
+
+        // The edge of the world.
+        player.travel(&model.portal, nalgebra::Vector3::new(0., 1000., 0.));
+        assert!(player.pos.norm() <= crate::modeling::WORLD_RADIUS + 1e-3);
+        // However fast.
+        player.travel(&model.portal, nalgebra::Vector3::new(0., -1e30, 0.));
+        assert!(player.pos.norm() <= crate::modeling::WORLD_RADIUS + 1e-3);
+    }
+
+    #[test]
+    fn falling_and_jumping() {
+        let mut model = Model::new();
+        model.key_down("KeyF");
+        assert_eq!(model.player.mode, Mode::Walk);
+
+        // Landing on the ground, and staying there.
+        let eye = GROUND_HEIGHT + EYE_HEIGHT;
+        for _ in 0..120 {
+            model.update(1. / 60.);
+            assert!(model.player.pos.z >= eye - 1e-5);
+        }
+        assert!((model.player.pos.z - eye).abs() < 1e-5);
+        assert!(model.player.standing);
+
+        // Jumping up and coming back down.
+        model.key_down("Space");
+        model.update(1. / 60.);
+        model.key_up("Space");
+        let mut highest = eye;
+        for _ in 0..120 {
+            model.update(1. / 60.);
+            highest = highest.max(model.player.pos.z);
+        }
+        assert!(highest > eye + 0.4);
+        assert!((model.player.pos.z - eye).abs() < 1e-5);
+
+        // Flying again.
+        model.key_up("KeyF");
+        model.key_down("KeyF");
+        assert_eq!(model.player.mode, Mode::Fly);
+        run(&mut model, 1.);
+        assert!((model.player.pos.z - eye).abs() < 1e-5);
+    }
+
+    #[test]
+    fn holding_the_toggle() {
+        let mut model = Model::new();
+        model.key_down("KeyF");
+        model.key_down("KeyF");
+        model.key_down("KeyF");
+        assert_eq!(model.player.mode, Mode::Walk);
+    }
+
+    #[test]
+    fn walking_under_the_frame() {
+        let mut model = Model::new();
+        model.player.pos = nalgebra::Vector3::new(5., 0.3, GROUND_HEIGHT + EYE_HEIGHT);
+        model.key_down("KeyF");
+        model.key_down("KeyW");
+        let start = model.player.pos;
+        for _ in 0..600 {
+            model.update(1. / 60.);
+        }
+
+        // Nothing was in the way, and going under the frame changed the world.
+        let mut world = 0;
+        let end = model.player.pos;
+        model.portal.travel(&mut world, start, end);
+        assert!((end - (start - nalgebra::Vector3::x() * 5.)).norm() < 1e-3);
+        assert_ne!(world, 0);
+        assert_eq!(model.player.world, world);
+
+        // And the crossings it went through are remembered.
+        let crossings: Vec<_> = model.recent_crossings().map(|&(_, c)| c).collect();
+        assert_eq!(crossings, model.portal.crossings(0, start, end),);
+        assert!(model
+            .recent_crossings()
+            .all(|&(tick, _)| tick < model.ticks()));
+    }
+
+    #[test]
+    fn settings() {
+        let mut model = Model::new();
+        model.settings.bindings.forward = vec!["KeyZ".to_string()];
+        model.settings.speed = 1.;
+        model.settings.invert_y = true;
+        let start = model.player.pos;
+
+        model.key_down("KeyW");
+        run(&mut model, 1.);
+        assert_eq!(model.player.pos, start);
+
+        model.key_up("KeyW");
+        model.key_down("KeyZ");
+        run(&mut model, 1.);
+        assert!((model.player.pos - (start - nalgebra::Vector3::x())).norm() < 1e-4);
+
+        model.look([0., 100.]);
+        assert!(model.player.phi > 0.);
+        assert_eq!(model.uniforms().fov, std::f32::consts::FRAC_PI_2);
+    }
+
+    #[test]
+    fn analog() {
+        let mut model = Model::new();
+        let start = model.player.pos;
+        model.input(Input::Analog(Analog {
+            movement: [0., 0.5],
+            look: [0., 1.],
+            vertical: 1.,
+        }));
+        run(&mut model, 1.);
+        let moved = start + nalgebra::Vector3::new(-0.25, 0., 0.5);
+        assert!((model.player.pos - moved).norm() < 1e-4);
+        assert!((model.player.phi - 1.).abs() < 1e-4);
+
+        // Pulling a trigger to jump.
+        model.input(Input::Analog(Analog::default()));
+        model.key_down("KeyF");
+        run(&mut model, 3.);
+        assert!(model.player.standing);
+        model.input(Input::Analog(Analog {
+            vertical: 0.7,
+            ..Analog::default()
+        }));
+        model.tick();
+        assert!(model.player.vertical_speed > 0.);
+    }
+
+    #[test]
+    fn looking_up_stops_at_vertical() {
+        let mut model = Model::new();
+        model.look([0., -1e6]);
+        assert!(model.player.phi < std::f32::consts::FRAC_PI_2);
+        assert!(model
+            .player
+            .isometry()
+            .rotation
+            .coords
+            .iter()
+            .all(|x| x.is_finite()));
+    }
+
+    // Play the same input at the given frame times, which are all shorter than a tick, returning the state after each tick.
+    fn states(frame_times: &[f32]) -> Vec<Snapshot> {
+        let mut model = Model::from_json(include_str!("../../scenes/physics.json")).unwrap();
+        model.key_down("KeyF");
+        let mut out = vec![model.snapshot()];
+        for &dt in frame_times.iter().cycle() {
+            model.update(dt);
+            if model.ticks() == out.len() as u64 {
+                out.push(model.snapshot());
+                match model.ticks() {
+                    60 => model.key_down("KeyW"),
+                    90 => model.key_down("Space"),
+                    120 => model.look([200., 0.]),
+                    180 => model.key_up("KeyW"),
+                    240 => return out,
+                    _ => {}
+                }
+            }
+        }
+        unreachable!()
+    }
+
+    #[test]
+    fn same_input_same_state() {
+        let states = states(&[1. / 144.]);
+        assert_eq!(states, self::states(&[1. / 144.]));
+        assert_eq!(states, self::states(&[0.004, 0.0161, 0.011, 0.0007, 0.009]));
+    }
+
+    #[test]
+    fn scripting() {
+        let mut model = Model::new();
+        assert!(model.set_player_pose([0., 0., 2.], 0., 0., 6).is_err());
+        model.set_player_pose([5., 0.3, 1.], 1., 0.5, 2).unwrap();
+        assert_eq!(model.player.pos, nalgebra::Vector3::new(5., 0.3, 1.));
+        assert_eq!((model.player.theta, model.player.phi), (1., 0.5));
+        assert_eq!(model.uniforms().player_world, 2);
+
+        // The scene's three balls are 0, 1 and 2.
+        let id = model
+            .add_ball([1., 0., 0., 1.], 4, [5., 0., 3.], [-3., 0., 0.])
+            .unwrap();
+        assert_eq!(id, 3);
+        // It's drawn straight away, before the next tick.
+        let one_ball = modeling::ball(nalgebra::Vector3::zeros(), 0, [1.; 4], 6).count();
+        assert_eq!(model.dynamic_geometry().len(), 4 * one_ball);
+        model.remove_ball(1).unwrap();
+        assert!(model.remove_ball(1).is_err());
+        assert_eq!(
+            model.balls.iter().map(|ball| ball.id).collect::<Vec<_>>(),
+            [0, 2, 3]
+        );
+
+        // The new ball flies under the frame.
+        run(&mut model, 3.);
+        let changes = model.take_world_changes();
+        assert_eq!(
+            changes[0],
+            WorldChange {
+                tick: 0,
+                who: Traveller::Player,
+                from: 0,
+                to: 2
+            }
+        );
+        assert!(changes
+            .iter()
+            .any(|change| change.who == Traveller::Ball(3) && change.from == 4));
+        assert_eq!(model.take_world_changes(), []);
+
+        // Scripted input is recorded like the rest.
+        model.replay().check().unwrap();
+    }
+
+    #[test]
+    fn stalls_skip_time() {
+        let mut model = Model::new();
+        model.update(10.);
+        assert_eq!(model.ticks(), (MAX_FRAME / TICK).round() as u64);
+    }
+
+    #[test]
+    fn releasing_keys() {
+        let mut model = Model::new();
+        model.key_down("Space");
+        model.key_down("KeyW");
+        model.release_keys();
+        assert!(model.keys.is_empty());
+        let inputs: Vec<Input> = model.replay().inputs.into_iter().map(|e| e.input).collect();
+        assert_eq!(
+            inputs[2..],
+            [
+                Input::KeyUp("KeyW".to_string()),
+                Input::KeyUp("Space".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn drawn_between_ticks() {
+        let mut model = Model::new();
+        model.key_down("KeyW");
+        model.update(TICK);
+        let before = model.player.pos;
+        model.update(TICK);
+        let after = model.player.pos;
+
+        model.update(TICK / 4.);
+        assert_eq!(model.player.pos, after);
+        let drawn = model.uniforms().player_isometry.translation.vector;
+        assert!((drawn - before.lerp(&after, 0.25)).norm() < 1e-6);
+    }
+}
@@ -0,0 +1,274 @@
+use crate::knot::Knot;
+use crate::portal::Portal;
+
+// A colour for each world, shared by all the triangles that look the same.
+// A triangle is invisible in any world past the end of its palette.
+pub type Palette = std::sync::Arc<[[f32; 4]]>;
+
+#[derive(Clone)]
+pub struct Triangle {
+    pub vertices: [nalgebra::Vector3<f32>; 3],
+    pub center: Option<nalgebra::Vector3<f32>>,
+
+    pub colors: Palette,
+
+    pub ambient_factor: f32,
+    pub diffuse_factor: f32,
+}
+
+impl Triangle {
+    pub fn center(&self) -> nalgebra::Vector3<f32> {
+        if let Some(center) = self.center {
+            center
+        } else {
+            let [v1, v2, v3] = self.vertices;
+            (v1 + v2 + v3) / 3.0
+        }
+    }
+}
+
+// Everything a renderer needs to draw a frame, besides the triangles.
+pub struct Uniforms {
+    pub player_isometry: nalgebra::Isometry3<f32>, // Player space -> World Space
+    pub player_world: i32,
+    pub light_dir: nalgebra::Vector3<f32>,
+    // Vertical, in radians.
+    pub fov: f32,
+}
+
+// The sky colours of the first six worlds. See `world_colors`.
+pub const WORLD_COLORS: [[f32; 4]; 6] = [
+    [0.2, 0.7, 1.0, 1.0],
+    [0.2, 1.0, 0.7, 1.0],
+    [0.7, 1.0, 0.2, 1.0],
+    [0.7, 0.2, 1.0, 1.0],
+    [1.0, 0.2, 0.7, 1.0],
+    [1.0, 0.7, 0.2, 1.0],
+];
+
+pub const GROUND_COLOR: [f32; 4] = [0.5, 0.5, 0.5, 1.0];
+
+pub const TUBE_RADIUS: f64 = 0.2;
+pub const GROUND_HEIGHT: f32 = -2.;
+// How far the player can go from the origin. The skybox is a tetrahedron whose faces are about 58 from the origin.
+pub const WORLD_RADIUS: f32 = 50.;
+// The distance from the centre of a ball to its vertices.
+pub const BALL_RADIUS: f32 = 0.19;
+
+// A different sky colour for each of `worlds` worlds.
+// Up to six, they're `WORLD_COLORS`. Past that, they're spread evenly around the colour wheel, as bright and as saturated.
+pub fn world_colors(worlds: i32) -> Vec<[f32; 4]> {
+    if worlds <= 6 {
+        return WORLD_COLORS[..worlds.max(0) as usize].to_vec();
+    }
+    (0..worlds)
+        .map(|w| {
+            let hue = 6. * w as f32 / worlds as f32;
+            let channel = |offset: f32| {
+                let distance = ((hue - offset).rem_euclid(6.) - 3.).abs();
+                0.2 + 0.8 * (distance - 1.).clamp(0., 1.)
+            };
+            [channel(0.), channel(2.), channel(4.), 1.]
+        })
+        .collect()
+}
+
+// Warning: theta = 0 is on the seam between worlds.
+pub fn knot_tube(knot: &Knot, t: f64, theta: f64) -> nalgebra::Vector3<f32> {
+    let [dx, dy, _]: [f64; 3] = knot.tangent(t).into();
+
+    let (s, c) = theta.sin_cos();
+    nalgebra::convert(
+        knot.point(t)
+            + TUBE_RADIUS
+                * (nalgebra::Vector3::new(dy, -dx, 0.).normalize() * s
+                    - nalgebra::Vector3::z() * c),
+    )
+}
+
+// The tube around the portal's frame. Each component of a link gets its own tube.
+// Looking through the portal, you see the sky of another world, so the tube's inside is coloured with `sky`, which has a colour per world.
+pub fn knot<'a>(portal: &'a Portal, sky: &[[f32; 4]]) -> impl Iterator<Item = Triangle> + 'a {
+    // In each world, the tube along an arc takes the sky colour of the world on the other side of it.
+    let palettes: Vec<Palette> = (0..portal.knot.arcs())
+        .map(|arc| {
+            (0..portal.worlds())
+                .map(|world| sky[portal.transition(arc, true, world) as usize])
+                .collect()
+        })
+        .collect();
+    (0..portal.knot.components()).flat_map(move |c| component_tube(portal, c, palettes.clone()))
+}
+
+fn component_tube(
+    portal: &Portal,
+    c: usize,
+    palettes: Vec<Palette>,
+) -> impl Iterator<Item = Triangle> + '_ {
+    const TAU: f64 = 2. * std::f64::consts::PI;
+
+    let ambient_factor = 0.2;
+    let diffuse_factor = 0.8;
+
+    let knot = &portal.knot;
+    let pieces = 32 * knot.component_arcs(c).len().max(3);
+
+    let f = move |a: usize, b: usize| {
+        let t = knot.param_on(c, a as f64 * TAU / pieces as f64);
+        let u = (4 * b + 1) as f64 * TAU / 48.;
+        knot_tube(knot, t, 4. * t + u)
+    };
+
+    (0..pieces).flat_map(move |a| {
+        let arc = knot.arc(knot.param_on(c, (a as f64 + 0.5) * TAU / pieces as f64));
+        let colors = palettes[arc].clone();
+
+        (0..12).flat_map(move |b| {
+            let v0 = f(a, b);
+            let v1 = f(a + 1, b);
+            let v2 = f(a, b + 1);
+            let v3 = f(a + 1, b + 1);
+
+            let t0 = Triangle {
+                vertices: [v0, v1, v2],
+                center: None,
+                colors: colors.clone(),
+                ambient_factor,
+                diffuse_factor,
+            };
+            let t1 = Triangle {
+                vertices: [v3, v2, v1],
+                center: None,
+                colors: colors.clone(),
+                ambient_factor,
+                diffuse_factor,
+            };
+
+            std::iter::once(t0).chain(std::iter::once(t1))
+        })
+    })
+}
+
+pub fn skybox(colors: Palette) -> impl IntoIterator<Item = Triangle> {
+    let ambient_factor = 1.0;
+    let diffuse_factor = 0.0;
+
+    let v0 = nalgebra::Vector3::new(-100., -100., 100.);
+    let v1 = nalgebra::Vector3::new(-100., 100., -100.);
+    let v2 = nalgebra::Vector3::new(100., -100., -100.);
+    let v3 = nalgebra::Vector3::new(100., 100., 100.);
+    vec![
+        Triangle {
+            vertices: [v2, v1, v0],
+            center: None,
+            colors: colors.clone(),
+            ambient_factor,
+            diffuse_factor,
+        },
+        Triangle {
+            vertices: [v0, v1, v3],
+            center: None,
+            colors: colors.clone(),
+            ambient_factor,
+            diffuse_factor,
+        },
+        Triangle {
+            vertices: [v3, v2, v0],
+            center: None,
+            colors: colors.clone(),
+            ambient_factor,
+            diffuse_factor,
+        },
+        Triangle {
+            vertices: [v1, v2, v3],
+            center: None,
+            colors: colors.clone(),
+            ambient_factor,
+            diffuse_factor,
+        },
+    ]
+}
+
+pub fn ground(colors: Palette) -> impl IntoIterator<Item = Triangle> {
+    let ambient_factor = 0.2;
+    let diffuse_factor = 0.8;
+
+    let v0 = nalgebra::Vector3::new(-100., -100., GROUND_HEIGHT);
+    let v1 = nalgebra::Vector3::new(100., -100., GROUND_HEIGHT);
+    let v2 = nalgebra::Vector3::new(100., 100., GROUND_HEIGHT);
+    let v3 = nalgebra::Vector3::new(-100., 100., GROUND_HEIGHT);
+    vec![
+        Triangle {
+            vertices: [v0, v1, v2],
+            center: None,
+            colors: colors.clone(),
+            ambient_factor,
+            diffuse_factor,
+        },
+        Triangle {
+            vertices: [v2, v3, v0],
+            center: None,
+            colors: colors.clone(),
+            ambient_factor,
+            diffuse_factor,
+        },
+    ]
+}
+
+// A ball, seen only in `world` of `worlds`.
+pub fn ball(
+    center: nalgebra::Vector3<f32>,
+    world: i32,
+    color: [f32; 4],
+    worlds: i32,
+) -> impl Iterator<Item = Triangle> {
+    let colors: Palette = (0..worlds)
+        .map(|w| if w == world { color } else { [0.0; 4] })
+        .collect();
+
+    const PHI: f32 = 1.618_034;
+
+    let ur = center + 0.1 * nalgebra::Vector3::new(1.0, 0.0, PHI);
+    let dr = center + 0.1 * nalgebra::Vector3::new(1.0, 0.0, -PHI);
+    let ul = center + 0.1 * nalgebra::Vector3::new(-1.0, 0.0, PHI);
+    let dl = center + 0.1 * nalgebra::Vector3::new(-1.0, 0.0, -PHI);
+    let rf = center + 0.1 * nalgebra::Vector3::new(PHI, 1.0, 0.0);
+    let lf = center + 0.1 * nalgebra::Vector3::new(-PHI, 1.0, 0.0);
+    let rb = center + 0.1 * nalgebra::Vector3::new(PHI, -1.0, 0.0);
+    let lb = center + 0.1 * nalgebra::Vector3::new(-PHI, -1.0, 0.0);
+    let fu = center + 0.1 * nalgebra::Vector3::new(0.0, PHI, 1.0);
+    let bu = center + 0.1 * nalgebra::Vector3::new(0.0, -PHI, 1.0);
+    let fd = center + 0.1 * nalgebra::Vector3::new(0.0, PHI, -1.0);
+    let bd = center + 0.1 * nalgebra::Vector3::new(0.0, -PHI, -1.0);
+
+    vec![
+        [ul, ur, fu],
+        [ur, ul, bu],
+        [dl, dr, bd],
+        [dr, dl, fd],
+        [rb, rf, ur],
+        [rf, rb, dr],
+        [lb, lf, dl],
+        [lf, lb, ul],
+        [fd, fu, rf],
+        [fu, fd, lf],
+        [bd, bu, lb],
+        [bu, bd, rb],
+        [fu, lf, ul],
+        [fu, ur, rf],
+        [fd, dl, lf],
+        [fd, rf, dr],
+        [bu, ul, lb],
+        [bu, rb, ur],
+        [bd, lb, dl],
+        [bd, dr, rb],
+    ]
+    .into_iter()
+    .map(move |vertices| Triangle {
+        vertices,
+        center: Some(center),
+        colors: colors.clone(),
+        ambient_factor: 0.2,
+        diffuse_factor: 0.8,
+    })
+}
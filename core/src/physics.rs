@@ -0,0 +1,216 @@
+// Free balls: they fall under gravity, and bounce off the ground, the portal's frame, and each other.
+//
+// Everything moves in short steps, and each step goes through `Portal::travel`,
+// so a ball changes world exactly when its motion takes it under the frame.
+// Two balls only collide if they're in the same world. In different worlds, they pass straight through each other.
+
+use crate::modeling::{BALL_RADIUS, GROUND_HEIGHT, TUBE_RADIUS};
+use crate::portal::Portal;
+use crate::{Ball, Motion};
+use nalgebra::Vector3;
+
+pub const GRAVITY: f32 = 2.;
+
+// The fraction of its speed a ball keeps when it bounces.
+const RESTITUTION: f32 = 0.8;
+
+// The longest step. At any reasonable speed, a ball moves much less than its radius in this time,
+// so it can't skip through the frame or another ball.
+const MAX_STEP: f32 = 1. / 240.;
+
+// Move all the balls forward by `dt` seconds.
+pub fn step(portal: &Portal, balls: &mut [Ball], dt: f32) {
+    let steps = (dt / MAX_STEP).ceil().max(1.);
+    for _ in 0..steps as usize {
+        for ball in balls.iter_mut() {
+            ball.travel(portal, dt / steps);
+        }
+        collide(portal, balls);
+    }
+}
+
+// Where a free ball at `pos` is after `dt` seconds, updating its velocity.
+pub(crate) fn fall(
+    portal: &Portal,
+    pos: Vector3<f32>,
+    velocity: &mut Vector3<f32>,
+    dt: f32,
+) -> Vector3<f32> {
+    velocity.z -= GRAVITY * dt;
+    let pos = pos + *velocity * dt;
+    push_out(portal, pos, BALL_RADIUS, |normal| bounce(velocity, normal))
+}
+
+// Push a sphere centred at `pos` out of the ground and the portal's frame,
+// calling `hit` with the normal of each surface it was pushed out of.
+pub(crate) fn push_out(
+    portal: &Portal,
+    mut pos: Vector3<f32>,
+    radius: f32,
+    mut hit: impl FnMut(Vector3<f32>),
+) -> Vector3<f32> {
+    let lowest = GROUND_HEIGHT + radius;
+    if pos.z < lowest {
+        pos.z = lowest;
+        hit(Vector3::z());
+    }
+
+    // Where two strands of the frame pass close to each other, pushing out of one can push into the other.
+    let closest = TUBE_RADIUS + f64::from(radius);
+    for _ in 0..4 {
+        let p: Vector3<f64> = nalgebra::convert(pos);
+        let nearest = portal.knot.nearest(p);
+        if (p - nearest).norm() >= closest {
+            break;
+        }
+        let normal = (p - nearest).normalize();
+        pos = nalgebra::convert(nearest + normal * closest);
+        hit(nalgebra::convert(normal));
+    }
+
+    pos
+}
+
+// Reflect the part of `velocity` heading into a surface, losing some speed.
+fn bounce(velocity: &mut Vector3<f32>, normal: Vector3<f32>) {
+    let into = velocity.dot(&normal);
+    if into < 0. {
+        *velocity -= normal * into * (1. + RESTITUTION);
+    }
+}
+
+// Push apart overlapping balls in the same world, and bounce them off each other.
+fn collide(portal: &Portal, balls: &mut [Ball]) {
+    for j in 1..balls.len() {
+        let (before, after) = balls.split_at_mut(j);
+        let b = &mut after[0];
+        for a in before.iter_mut() {
+            if a.world != b.world {
+                continue;
+            }
+
+            // Balls following paths can't be pushed around, so they act as if infinitely heavy.
+            // Otherwise, all balls weigh the same.
+            let (share_a, share_b) = match (&a.motion, &b.motion) {
+                (Motion::Free, Motion::Free) => (0.5, 0.5),
+                (Motion::Free, Motion::Path(_)) => (1., 0.),
+                (Motion::Path(_), Motion::Free) => (0., 1.),
+                (Motion::Path(_), Motion::Path(_)) => continue,
+            };
+
+            let d = b.pos - a.pos;
+            let distance = d.norm();
+            if distance >= 2. * BALL_RADIUS || distance == 0. {
+                continue;
+            }
+            let normal = d / distance;
+
+            let approach = (a.velocity - b.velocity).dot(&normal);
+            if approach > 0. {
+                let impulse = normal * approach * (1. + RESTITUTION);
+                a.velocity -= impulse * share_a;
+                b.velocity += impulse * share_b;
+            }
+
+            let overlap = 2. * BALL_RADIUS - distance;
+            shift(portal, a, -normal * overlap * share_a);
+            shift(portal, b, normal * overlap * share_b);
+        }
+    }
+}
+
+fn shift(portal: &Portal, ball: &mut Ball, d: Vector3<f32>) {
+    let pos = ball.pos + d;
+    portal.travel(&mut ball.world, ball.pos, pos);
+    ball.pos = pos;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::knot::Knot;
+
+    const WHITE: [f32; 4] = [1.; 4];
+
+    fn run(portal: &Portal, balls: &mut [Ball], seconds: f32, mut check: impl FnMut(&[Ball])) {
+        for _ in 0..(seconds * 60.) as usize {
+            step(portal, balls, 1. / 60.);
+            check(balls);
+        }
+    }
+
+    #[test]
+    fn bouncing_on_the_ground() {
+        let portal = Portal::trefoil();
+        let mut balls = [Ball::free(
+            WHITE,
+            0,
+            Vector3::new(10., 0., 2.),
+            Vector3::zeros(),
+        )];
+        let mut peaks = Vec::new();
+        let mut rising = false;
+        run(&portal, &mut balls, 10., |balls| {
+            assert!(balls[0].pos.z >= GROUND_HEIGHT + BALL_RADIUS);
+            if rising && balls[0].velocity.z <= 0. {
+                peaks.push(balls[0].pos.z);
+            }
+            rising = balls[0].velocity.z > 0.;
+        });
+        assert!(peaks.len() >= 3);
+        assert!(peaks.windows(2).all(|w| w[1] < w[0]));
+    }
+
+    #[test]
+    fn landing_on_the_frame() {
+        let portal = Portal::trefoil();
+        // Dropped onto the top of the tube.
+        let top = portal.knot.point(std::f64::consts::PI / 6.);
+        let mut balls = [Ball::free(
+            WHITE,
+            0,
+            nalgebra::convert(top + Vector3::new(0., 0., 1.)),
+            Vector3::zeros(),
+        )];
+        let mut lowest = f64::INFINITY;
+        run(&portal, &mut balls, 3., |balls| {
+            let p = nalgebra::convert(balls[0].pos);
+            lowest = lowest.min((portal.knot.nearest(p) - p).norm());
+        });
+        let closest = TUBE_RADIUS + f64::from(BALL_RADIUS);
+        assert!(lowest > closest - 1e-3);
+        assert!(lowest < closest + 1e-2, "never reached the frame");
+    }
+
+    #[test]
+    fn rolling_under_the_frame() {
+        // Rolling along the ground, a ball goes under the trefoil's arcs, and must change world as if it had moved in a straight line.
+        let portal = Portal::trefoil();
+        let start = Vector3::new(5., 0.3, GROUND_HEIGHT + BALL_RADIUS);
+        let mut balls = [Ball::free(WHITE, 0, start, Vector3::new(-2., 0., 0.))];
+        run(&portal, &mut balls, 2.5, |_| {});
+
+        let mut world = 0;
+        let mut end = balls[0].pos;
+        end.z = start.z;
+        portal.travel(&mut world, start, end);
+        assert!(end.x.abs() < 0.1);
+        assert_ne!(world, 0);
+        assert_eq!(balls[0].world, world);
+    }
+
+    #[test]
+    fn collisions_need_the_same_world() {
+        let portal = Portal::new(Knot::trefoil(), vec![vec![1, 0]; 3]);
+        for &(worlds, collide) in &[((0, 0), true), ((0, 1), false)] {
+            // Far from the knot, rolling towards each other.
+            let z = GROUND_HEIGHT + BALL_RADIUS;
+            let mut balls = [
+                Ball::free(WHITE, worlds.0, Vector3::new(10., -1., z), Vector3::y()),
+                Ball::free(WHITE, worlds.1, Vector3::new(10., 1., z), -Vector3::y()),
+            ];
+            run(&portal, &mut balls, 2., |_| {});
+            assert_eq!(balls[0].pos.y < balls[1].pos.y, collide);
+        }
+    }
+}
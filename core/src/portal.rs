@@ -0,0 +1,438 @@
+pub mod reference;
+
+use crate::knot::Knot;
+
+// A portal whose frame is a knot, or a link of several knots.
+//
+// Passing under an arc of the diagram causes you to switch worlds, whichever component the arc is on.
+// Passing under arc `k` from its left to its right (looking down on it, along the direction of increasing parameter)
+// sends world `w` to world `permutations[k][w]`. Passing under it from right to left undoes that.
+//
+// For this to be consistent, going around any of the knot's crossings mustn't change your world.
+// So the permutations must satisfy the Wirtinger relations: they are a homomorphism from the knot group to the permutations of the worlds.
+pub struct Portal {
+    pub knot: Knot,
+    permutations: Vec<Vec<i32>>,
+    inverses: Vec<Vec<i32>>,
+}
+
+impl Portal {
+    pub fn new(knot: Knot, permutations: Vec<Vec<i32>>) -> Self {
+        Self::try_new(knot, permutations).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    // Like `new`, but says what's wrong with the permutations instead of panicking.
+    pub fn try_new(knot: Knot, permutations: Vec<Vec<i32>>) -> Result<Self, String> {
+        if permutations.len() != knot.arcs() {
+            return Err(format!(
+                "one permutation per arc: the knot has {} arcs, but there are {} permutations",
+                knot.arcs(),
+                permutations.len()
+            ));
+        }
+        let worlds = permutations[0].len();
+        if worlds == 0 {
+            return Err("no worlds".to_string());
+        }
+
+        let inverses = permutations
+            .iter()
+            .map(|perm| {
+                if perm.len() != worlds {
+                    return Err("every permutation must act on all the worlds".to_string());
+                }
+                let mut inverse = vec![-1; worlds];
+                for (w, &image) in perm.iter().enumerate() {
+                    if !(0..worlds as i32).contains(&image) || inverse[image as usize] != -1 {
+                        return Err(format!("{:?} is not a permutation", perm));
+                    }
+                    inverse[image as usize] = w as i32;
+                }
+                Ok(inverse)
+            })
+            .collect::<Result<Vec<Vec<i32>>, String>>()?;
+
+        let portal = Self {
+            knot,
+            permutations,
+            inverses,
+        };
+
+        // Walk around a small loop, counterclockwise, just below each crossing.
+        // It passes under both strands on both sides of the crossing, and can be slid down away from the knot,
+        // so it mustn't change your world.
+        for c in portal.knot.crossings() {
+            let over = portal.knot.tangent(c.over).xy();
+            let under = portal.knot.tangent(c.under).xy();
+
+            // Heading counterclockwise, the loop passes under the forward half of each strand from right to left,
+            // and the backward half from left to right.
+            let loop_around = if over.perp(&under) > 0. {
+                [
+                    (c.over_arc, false),
+                    (c.arc_out, false),
+                    (c.over_arc, true),
+                    (c.arc_in, true),
+                ]
+            } else {
+                [
+                    (c.over_arc, false),
+                    (c.arc_in, true),
+                    (c.over_arc, true),
+                    (c.arc_out, false),
+                ]
+            };
+
+            for start in 0..portal.worlds() {
+                let mut world = start;
+                for &(arc, left_to_right) in &loop_around {
+                    world = portal.transition(arc, left_to_right, world);
+                }
+                if world != start {
+                    return Err(format!("inconsistent at the crossing at {:?}", c.pos));
+                }
+            }
+        }
+
+        Ok(portal)
+    }
+
+    // The portal from the knot's smallest Fox colouring, with a world for each colour.
+    // Passing under an arc of colour `c` sends world `w` to world `2c - w` (mod p).
+    pub fn fox(knot: Knot) -> Self {
+        let (p, colours) = knot
+            .fox_colouring()
+            .expect("this knot has no Fox colourings with a small prime");
+        let permutations = colours
+            .into_iter()
+            .map(|c| (0..p).map(|w| (2 * c - w).rem_euclid(p)).collect())
+            .collect();
+        Self::new(knot, permutations)
+    }
+
+    // Like `fox`, but with twice as many worlds, so you can tell which side of the portal you came in from.
+    // A colour `c` (mod p) becomes the arc value `a = 2c + 1` (mod 2p), sending world `w` to world `a - w`.
+    pub fn dihedral(knot: Knot) -> Self {
+        let (p, colours) = knot
+            .fox_colouring()
+            .expect("this knot has no Fox colourings with a small prime");
+        let arc_values = colours.into_iter().map(|c| 2 * c + 1).collect();
+        Self::reflections(knot, 2 * p, arc_values)
+    }
+
+    // Passing under arc `k` sends world `w` to world `arc_values[k] - w` (mod `worlds`).
+    fn reflections(knot: Knot, worlds: i32, arc_values: Vec<i32>) -> Self {
+        let permutations = arc_values
+            .into_iter()
+            .map(|a| (0..worlds).map(|w| (a - w).rem_euclid(worlds)).collect())
+            .collect();
+        Self::new(knot, permutations)
+    }
+
+    // A world for each element of the group generated by `generators`, which are the images of the arcs.
+    // Passing under arc `k` from left to right sends the world of `g` to the world of `generators[k] * g`.
+    pub fn regular<G: PartialEq>(
+        knot: Knot,
+        generators: Vec<G>,
+        mul: impl Fn(&G, &G) -> G,
+    ) -> Self {
+        // In a finite group, the products of the generators are the whole group.
+        let mut elements: Vec<G> = Vec::new();
+        for g in &generators {
+            let g = mul(g, &generators[0]);
+            if !elements.contains(&g) {
+                elements.push(g);
+            }
+        }
+        let mut i = 0;
+        while i < elements.len() {
+            for g in &generators {
+                let h = mul(g, &elements[i]);
+                if !elements.contains(&h) {
+                    elements.push(h);
+                }
+            }
+            i += 1;
+        }
+
+        let permutations = generators
+            .iter()
+            .map(|g| {
+                elements
+                    .iter()
+                    .map(|h| {
+                        let gh = mul(g, h);
+                        elements.iter().position(|x| *x == gh).unwrap() as i32
+                    })
+                    .collect()
+            })
+            .collect();
+        Self::new(knot, permutations)
+    }
+
+    /*
+     ┏━━┓  ┏━━┓
+    ┏┛  ┗┓┏┛  ┗┓
+    ┃    ┗┓    ┃
+    ┃   ┏┛┗┓   ┃
+    ┗┓ ┏┛  ┗┓ ┏┛
+     ┗━┃━━━━━━┛
+       ┃    ┃
+       ┗┓  ┏┛
+        ┗━━┛
+
+    The trefoil is parameterized by (sin(t) + 2sin(2t), cos(t)-2cos(2t), sin(3t)).
+
+    (A = top left, B = right, C = bottom)
+
+
+    Passing under an arc causes you to switch worlds.
+
+          ╔═══╗       ╔═══╗
+          ║ 1 ║───C───║ 2 ║
+          ╚═══╝       ╚═══╝
+          ╱   ╲       ╱   ╲
+         A     B     A     B
+        ╱       ╲   ╱       ╲
+    ╔═══╗        ╲ ╱        ╔═══╗
+    ║ 0 ║───C─────╳─────C───║ 3 ║
+    ╚═══╝        ╱ ╲        ╚═══╝
+        ╲       ╱   ╲       ╱
+         B     A     B     A
+          ╲   ╱       ╲   ╱
+          ╔═══╗       ╔═══╗
+          ║ 5 ║───C───║ 4 ║
+          ╚═══╝       ╚═══╝
+    */
+    pub fn trefoil() -> Self {
+        // The trefoil's arcs, in order of parameter, are C, A, B.
+        // Arc A = 1, B = 5, C = 3
+        Self::reflections(Knot::trefoil(), 6, vec![3, 1, 5])
+    }
+
+    // The trefoil, with the arcs acting as rotations of a tetrahedron, permuting its four vertices.
+    pub fn trefoil_tetrahedral() -> Self {
+        Self::new(
+            Knot::trefoil(),
+            vec![vec![1, 2, 0, 3], vec![2, 1, 3, 0], vec![0, 3, 1, 2]],
+        )
+    }
+
+    // The trefoil, with a world for each of the 120 elements of the binary icosahedral group, SL(2, 5).
+    // The arcs act as the matrices below, which are all conjugate to [[1, 1], [0, 1]].
+    pub fn trefoil_icosahedral() -> Self {
+        let mul = |a: &[i32; 4], b: &[i32; 4]| {
+            [
+                (a[0] * b[0] + a[1] * b[2]) % 5,
+                (a[0] * b[1] + a[1] * b[3]) % 5,
+                (a[2] * b[0] + a[3] * b[2]) % 5,
+                (a[2] * b[1] + a[3] * b[3]) % 5,
+            ]
+        };
+        Self::regular(
+            Knot::trefoil(),
+            vec![[1, 1, 0, 1], [0, 1, 4, 2], [1, 0, 4, 1]],
+            mul,
+        )
+    }
+
+    // The Hopf link, with a world for each pair (a mod 3, b mod 2).
+    // Passing through the first ring steps a, and passing through the second steps b.
+    pub fn hopf() -> Self {
+        Self::regular(Knot::hopf(), vec![(1, 0), (0, 1)], |x, y| {
+            ((x.0 + y.0) % 3, (x.1 + y.1) % 2)
+        })
+    }
+
+    // The Borromean rings, with the arcs acting as 5-cycles on five worlds.
+    // Passing through two of the rings and back again, in the wrong order, changes your world,
+    // even though neither ring is linked with the other.
+    pub fn borromean() -> Self {
+        Self::new(
+            Knot::borromean(),
+            vec![
+                vec![3, 2, 0, 4, 1],
+                vec![4, 0, 1, 2, 3],
+                vec![2, 4, 3, 1, 0],
+                vec![3, 0, 4, 2, 1],
+                vec![3, 2, 4, 1, 0],
+                vec![1, 3, 0, 4, 2],
+            ],
+        )
+    }
+
+    // The Whitehead link, with the arcs acting as 5-cycles on five worlds.
+    pub fn whitehead() -> Self {
+        Self::new(
+            Knot::whitehead(),
+            vec![
+                vec![2, 4, 1, 0, 3],
+                vec![3, 0, 4, 2, 1],
+                vec![1, 2, 3, 4, 0],
+                vec![3, 2, 4, 1, 0],
+                vec![4, 3, 0, 2, 1],
+            ],
+        )
+    }
+
+    pub fn worlds(&self) -> i32 {
+        self.permutations[0].len() as i32
+    }
+
+    // Where passing under arc `arc` from left to right takes each world.
+    pub fn permutation(&self, arc: usize) -> &[i32] {
+        &self.permutations[arc]
+    }
+
+    // Where passing under arc `arc` from right to left takes each world.
+    pub fn inverse(&self, arc: usize) -> &[i32] {
+        &self.inverses[arc]
+    }
+
+    // Which world do you end up in, if you pass under arc `arc` from world `world`?
+    pub fn transition(&self, arc: usize, left_to_right: bool, world: i32) -> i32 {
+        if left_to_right {
+            self.permutations[arc][world as usize]
+        } else {
+            self.inverses[arc][world as usize]
+        }
+    }
+
+    // If you travel in a straight line from `start` to `end`, in which world do you end up?
+    pub fn travel(
+        &self,
+        world: &mut i32,
+        start: nalgebra::Vector3<f32>,
+        end: nalgebra::Vector3<f32>,
+    ) {
+        if let Some(last) = self.crossings(*world, start, end).last() {
+            *world = last.to;
+        }
+    }
+
+    // If you travel in a straight line from `start` to `end`, starting in `world`, which arcs do you pass under, and where do they take you?
+    pub fn crossings(
+        &self,
+        mut world: i32,
+        start: nalgebra::Vector3<f32>,
+        end: nalgebra::Vector3<f32>,
+    ) -> Vec<Crossing> {
+        self.knot
+            .passes_under(nalgebra::convert(start), nalgebra::convert(end))
+            .into_iter()
+            .map(|(_, arc, left_to_right)| {
+                let from = world;
+                world = self.transition(arc, left_to_right, world);
+                Crossing {
+                    arc,
+                    left_to_right,
+                    from,
+                    to: world,
+                }
+            })
+            .collect()
+    }
+}
+
+// Passing under an arc of the frame, from one world to another.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Crossing {
+    pub arc: usize,
+    pub left_to_right: bool,
+    pub from: i32,
+    pub to: i32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Portal;
+    use crate::knot::Knot;
+    use nalgebra::Vector3;
+
+    #[test]
+    fn trefoil_representations() {
+        assert_eq!(Portal::fox(Knot::trefoil()).worlds(), 3);
+        assert_eq!(Portal::trefoil_tetrahedral().worlds(), 4);
+        assert_eq!(Portal::trefoil().worlds(), 6);
+        assert_eq!(Portal::trefoil_icosahedral().worlds(), 120);
+    }
+
+    #[test]
+    #[should_panic(expected = "inconsistent")]
+    fn wrong_way_round() {
+        // The tetrahedral portal, with the first arc's rotation reversed.
+        Portal::new(
+            Knot::trefoil(),
+            vec![vec![2, 0, 1, 3], vec![2, 1, 3, 0], vec![0, 3, 1, 2]],
+        );
+    }
+
+    #[test]
+    fn links() {
+        assert_eq!(Portal::hopf().worlds(), 6);
+        assert_eq!(Portal::borromean().worlds(), 5);
+        assert_eq!(Portal::whitehead().worlds(), 5);
+    }
+
+    #[test]
+    fn borromean_commutator() {
+        // Loop down through a ring: from above its centre, down, out under the ring, up, and back over it.
+        let portal = Portal::borromean();
+        let through = |world: &mut i32, k: u32, down: bool| {
+            let angle = std::f32::consts::FRAC_PI_2 + k as f32 * 2. * std::f32::consts::PI / 3.;
+            let (inside, outside) = (1., 4.);
+            let direction = Vector3::new(angle.cos(), angle.sin(), 0.);
+            let up = Vector3::new(0., 0., 3.);
+            let mut path = [
+                inside * direction + up,
+                inside * direction - up,
+                outside * direction - up,
+                outside * direction + up,
+                inside * direction + up,
+            ];
+            if !down {
+                path.reverse();
+            }
+            for i in 0..4 {
+                portal.travel(world, path[i], path[i + 1]);
+            }
+        };
+
+        // Through the first ring, then the second, then back up through the first, then the second.
+        // Neither ring is linked with the other, but the third ring holds them together.
+        let mut changed = false;
+        for start in 0..portal.worlds() {
+            let mut world = start;
+            for &(k, down) in &[(0, true), (1, true), (0, false), (1, false)] {
+                through(&mut world, k, down);
+            }
+            changed |= world != start;
+        }
+        assert!(changed);
+    }
+
+    #[test]
+    fn loops_below_the_knot() {
+        // A closed path below the whole knot can be pulled down away from it, so it must bring you back where you started.
+        // The icosahedral portal's permutations aren't their own inverses, so this also checks the directions.
+        let portal = Portal::trefoil_icosahedral();
+        assert_ne!(portal.permutation(0), portal.inverse(0));
+
+        let mut seed = 1u32;
+        let mut random = move || {
+            seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            (seed >> 8) as f32 / (1 << 24) as f32 * 8. - 4.
+        };
+
+        for start in 0..100 {
+            let corners: Vec<Vector3<f32>> = (0..3)
+                .map(|_| Vector3::new(random(), random(), -2.))
+                .collect();
+            let mut world = start;
+            for i in 0..3 {
+                portal.travel(&mut world, corners[i], corners[(i + 1) % 3]);
+            }
+            assert_eq!(world, start);
+        }
+    }
+}
@@ -0,0 +1,221 @@
+// A slow, independent way to decide which world a path ends in, for checking `Portal::trefoil().travel` against.
+//
+// `Portal::travel` refines each crossing on the curve itself, and takes its arcs and arc values from `Knot` and `Portal`.
+// This module shares none of that. It works only from the trefoil's parametrization:
+//
+//     1.) Sample the trefoil densely, as a closed polyline.
+//     2.) Find the crossings of its projection onto the xy-plane, and note which strand passes under.
+//     3.) Cut the knot at each undercrossing. The pieces are the arcs of the knot diagram.
+//         Each arc is named A, B or C after where its midpoint is, as in the diagram above `Portal::trefoil`.
+//         `Knot::trefoil`'s arcs, in order of parameter, are C, A and B, cut at undercrossings about 0.29, 0.62 and 0.96 of the way round.
+//     4.) Whenever the path passes under a piece of the polyline, apply that arc's Wirtinger generator.
+//
+// Each generator is a reflection of the six worlds, so it is its own inverse,
+// and it doesn't matter in which direction the path passes under the arc.
+//
+// The answer is exact, except for paths passing within about 1e-5 of the knot itself.
+
+use nalgebra::{Vector2, Vector3};
+
+// Samples per chunk. The chunks' bounding boxes let us skip most of the knot when intersecting.
+const CHUNK: usize = 64;
+const CHUNKS: usize = 96;
+const SAMPLES: usize = CHUNK * CHUNKS;
+
+// Arc A = 1, B = 5, C = 3
+const ARC_A: i32 = 1;
+const ARC_B: i32 = 5;
+const ARC_C: i32 = 3;
+
+struct Diagram {
+    // `points[i]` to `points[i + 1]` (cyclically) is a segment of the knot.
+    points: Vec<Vector3<f64>>,
+    // The arc containing the start of each segment.
+    arcs: Vec<i32>,
+    // The undercrossings: which segment passes under, how far along it, and the arc that starts there.
+    cuts: Vec<(usize, f64, i32)>,
+    // The xy bounding box of each chunk of segments.
+    boxes: Vec<(Vector2<f64>, Vector2<f64>)>,
+}
+
+fn diagram() -> &'static Diagram {
+    static DIAGRAM: std::sync::OnceLock<Diagram> = std::sync::OnceLock::new();
+    DIAGRAM.get_or_init(Diagram::new)
+}
+
+impl Diagram {
+    fn new() -> Self {
+        const TAU: f64 = 2. * std::f64::consts::PI;
+
+        let points: Vec<Vector3<f64>> = (0..SAMPLES)
+            .map(|i| {
+                let t = i as f64 * TAU / SAMPLES as f64;
+                trefoil(t)
+            })
+            .collect();
+
+        let boxes = (0..CHUNKS)
+            .map(|c| {
+                let chunk = (c * CHUNK..=(c + 1) * CHUNK).map(|i| points[i % SAMPLES].xy());
+                bounding_box(chunk)
+            })
+            .collect();
+
+        let mut diagram = Self {
+            points,
+            arcs: Vec::new(),
+            cuts: Vec::new(),
+            boxes,
+        };
+
+        // Find the undercrossings.
+        let mut cuts = Vec::new();
+        for i in 0..SAMPLES {
+            let [a, b] = diagram.segment(i);
+            for (j, s, _, height) in diagram.crossings(a, b) {
+                // Skip the segment itself and its neighbours, which share endpoints with it.
+                let gap = (i + SAMPLES - j) % SAMPLES;
+                if gap <= 1 || gap == SAMPLES - 1 {
+                    continue;
+                }
+                if height > 0.0 {
+                    cuts.push((i, s));
+                }
+            }
+        }
+        cuts.sort_by(|x, y| x.partial_cmp(y).unwrap());
+        assert!(!cuts.is_empty());
+
+        // Name each arc by where its midpoint is, as a fraction of the way round the knot. `Knot::trefoil` has arc C
+        // from the undercrossing at about 0.29 to the one at about 0.62, then arc A to about 0.96, then arc B back round to 0.29.
+        let mut arcs = vec![0; SAMPLES];
+        for (k, &(cut, cut_u)) in cuts.iter().enumerate() {
+            let next = cuts[(k + 1) % cuts.len()].0;
+            let len = (next + SAMPLES - cut) % SAMPLES;
+            let midpoint = (cut + len / 2) % SAMPLES;
+            let turn = midpoint as f64 / SAMPLES as f64;
+            let arc = if (0.29..0.62).contains(&turn) {
+                ARC_C
+            } else if (0.62..0.96).contains(&turn) {
+                ARC_A
+            } else {
+                ARC_B
+            };
+            for i in 0..len {
+                arcs[(cut + 1 + i) % SAMPLES] = arc;
+            }
+            diagram.cuts.push((cut, cut_u, arc));
+        }
+        diagram.arcs = arcs;
+
+        diagram
+    }
+
+    // The arc containing the point `u` of the way along segment `i`.
+    fn arc(&self, i: usize, u: f64) -> i32 {
+        self.cuts
+            .iter()
+            .find(|&&(cut, cut_u, _)| cut == i && u >= cut_u)
+            .map_or(self.arcs[i], |&(_, _, arc)| arc)
+    }
+
+    fn segment(&self, i: usize) -> [Vector3<f64>; 2] {
+        [self.points[i], self.points[(i + 1) % SAMPLES]]
+    }
+
+    // Every knot segment whose projection crosses the projection of `a` to `b`.
+    // Yields the segment's index, how far along `a` to `b` the crossing is,
+    // how far along the knot segment it is, and how far above the line from `a` to `b` the knot is there.
+    //
+    // Both segments are treated as half-open, so that a crossing at a shared endpoint of consecutive segments is only counted once.
+    fn crossings<'a>(
+        &'a self,
+        a: Vector3<f64>,
+        b: Vector3<f64>,
+    ) -> impl Iterator<Item = (usize, f64, f64, f64)> + 'a {
+        let (lo, hi) = bounding_box(vec![a.xy(), b.xy()]);
+
+        self.boxes
+            .iter()
+            .enumerate()
+            .filter(move |(_, (box_lo, box_hi))| {
+                lo.x <= box_hi.x && box_lo.x <= hi.x && lo.y <= box_hi.y && box_lo.y <= hi.y
+            })
+            .flat_map(|(c, _)| c * CHUNK..(c + 1) * CHUNK)
+            .filter_map(move |i| {
+                let [p, q] = self.segment(i);
+                let (s, u) = intersect(a.xy(), b.xy(), p.xy(), q.xy())?;
+                let z = a.z + s * (b.z - a.z);
+                let knot_z = p.z + u * (q.z - p.z);
+                Some((i, s, u, knot_z - z))
+            })
+    }
+}
+
+fn trefoil(t: f64) -> Vector3<f64> {
+    Vector3::new(
+        t.sin() + 2. * (2. * t).sin(),
+        t.cos() - 2. * (2. * t).cos(),
+        (3. * t).sin(),
+    )
+}
+
+fn bounding_box(points: impl IntoIterator<Item = Vector2<f64>>) -> (Vector2<f64>, Vector2<f64>) {
+    let mut lo = Vector2::repeat(f64::INFINITY);
+    let mut hi = Vector2::repeat(-f64::INFINITY);
+    for p in points {
+        lo = lo.zip_map(&p, f64::min);
+        hi = hi.zip_map(&p, f64::max);
+    }
+    (lo, hi)
+}
+
+// Where do the segments `a` to `b` and `p` to `q` cross?
+// Returns the fractions of the way along each segment, each in `0..1`.
+fn intersect(
+    a: Vector2<f64>,
+    b: Vector2<f64>,
+    p: Vector2<f64>,
+    q: Vector2<f64>,
+) -> Option<(f64, f64)> {
+    let ab = b - a;
+    let pq = q - p;
+    let ap = p - a;
+
+    let denom = ab.perp(&pq);
+    if denom == 0.0 {
+        return None;
+    }
+    let s = ap.perp(&pq) / denom;
+    let u = ap.perp(&ab) / denom;
+
+    if (0.0..1.0).contains(&s) && (0.0..1.0).contains(&u) {
+        Some((s, u))
+    } else {
+        None
+    }
+}
+
+// If you travel along `path` (a polyline), starting in `start_world`, in which world do you end up?
+pub fn travel_path(path: &[Vector3<f32>], start_world: i32) -> i32 {
+    let diagram = diagram();
+    let mut world = start_world;
+
+    for pair in path.windows(2) {
+        let a: Vector3<f64> = nalgebra::convert(pair[0]);
+        let b: Vector3<f64> = nalgebra::convert(pair[1]);
+
+        let mut under: Vec<(f64, i32)> = diagram
+            .crossings(a, b)
+            .filter(|&(_, _, _, height)| height > 0.0)
+            .map(|(i, s, u, _)| (s, diagram.arc(i, u)))
+            .collect();
+        under.sort_by(|x, y| x.0.partial_cmp(&y.0).unwrap());
+
+        for (_, arc) in under {
+            world = arc - world;
+        }
+    }
+
+    world.rem_euclid(6)
+}
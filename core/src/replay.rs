@@ -0,0 +1,284 @@
+// Recordings of the input to a game, which play back exactly.
+//
+// The game moves in fixed ticks, so the same input at the same ticks always gives the same result, down to the bit.
+// A replay stores the scene, each input with the tick it arrived before, and where the player and the balls ended up,
+// so playing it back can check that they end up in the same places and worlds.
+// Clicks only capture the mouse, and don't affect the game, so they aren't recorded.
+// See `replays/` for examples, which are also run as tests.
+
+use crate::model::Model;
+use crate::scene::{self, Scene};
+use crate::settings::Settings;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Input {
+    KeyDown(String),
+    KeyUp(String),
+    // A mouse movement, in pixels.
+    Look([f32; 2]),
+    // The gamepad's sticks and triggers, or the touch controls' joystick, moved. See `Analog::combine`.
+    Analog(Analog),
+    // From a script on the page, rather than the player. See `Model::set_player_pose`, `Model::add_ball` and `Model::remove_ball`.
+    SetPose {
+        position: [f32; 3],
+        theta: f32,
+        phi: f32,
+        world: i32,
+    },
+    AddBall {
+        color: [f32; 4],
+        world: i32,
+        position: [f32; 3],
+        velocity: [f32; 3],
+    },
+    RemoveBall(u32),
+}
+
+// Analog input, after the dead zone and the response curve. Each part is between -1 and 1, except `look`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Analog {
+    // Right and forward.
+    pub movement: [f32; 2],
+    // How fast to turn right and up, in radians per second.
+    pub look: [f32; 2],
+    // Up when flying, or jump when walking; down, or sprint.
+    pub vertical: f32,
+}
+
+impl Analog {
+    /// Two devices' analog input at once, such as a gamepad's and the touch controls', as though from one.
+    /// Each device sends all of its `Analog` whenever any of it changes, so the front-end keeps the latest from each, and sends this.
+    pub fn combine(self, other: Analog) -> Analog {
+        let [x, y] = self.movement;
+        let [u, v] = other.movement;
+        Analog {
+            movement: [(x + u).clamp(-1., 1.), (y + v).clamp(-1., 1.)],
+            look: [self.look[0] + other.look[0], self.look[1] + other.look[1]],
+            vertical: (self.vertical + other.vertical).clamp(-1., 1.),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Event {
+    // How many ticks the game had run for when the input arrived.
+    pub tick: u64,
+    #[serde(flatten)]
+    pub input: Input,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Replay {
+    // As in a scene file.
+    pub scene: serde_json::Value,
+    // The bindings, sensitivity and speed change what the input does.
+    #[serde(default)]
+    pub settings: Settings,
+    pub inputs: Vec<Event>,
+    // How long the game ran for.
+    pub ticks: u64,
+    pub end: End,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct End {
+    pub player: Place,
+    pub balls: Vec<Place>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Place {
+    pub position: [f32; 3],
+    pub world: i32,
+}
+
+impl std::fmt::Display for Place {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let [x, y, z] = self.position;
+        write!(f, "world {} at ({}, {}, {})", self.world, x, y, z)
+    }
+}
+
+impl End {
+    pub fn of(model: &Model) -> Self {
+        Self {
+            player: Place {
+                position: model.player.pos.into(),
+                world: model.player.world,
+            },
+            balls: model
+                .balls
+                .iter()
+                .map(|ball| Place {
+                    position: ball.pos.into(),
+                    world: ball.world,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Replay {
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    /// A model at the start of the replay, which plays the recorded input as it runs.
+    pub fn start(&self) -> Result<Model, scene::Error> {
+        let scene = Scene::deserialize(&self.scene)?;
+        let mut model = Model::from_scene(&scene, self.scene.clone())?;
+        model.settings = self.settings.clone();
+        model.queue(self.inputs.iter().cloned(), self.ticks);
+        Ok(model)
+    }
+
+    /// Play the whole replay, as fast as possible.
+    pub fn play(&self) -> Result<Model, scene::Error> {
+        let mut model = self.start()?;
+        for _ in 0..self.ticks {
+            model.tick();
+        }
+        model.flush();
+        Ok(model)
+    }
+
+    /// Play the whole replay, and check that everything ends up where it did when it was recorded.
+    pub fn check(&self) -> Result<(), String> {
+        let model = self.play().map_err(|e| e.to_string())?;
+        let end = End::of(&model);
+
+        let mut differences = Vec::new();
+        if end.player != self.end.player {
+            differences.push(format!(
+                "the player ended up in {}, not {}",
+                end.player, self.end.player
+            ));
+        }
+        if end.balls.len() != self.end.balls.len() {
+            differences.push(format!(
+                "there are {} balls, not {}",
+                end.balls.len(),
+                self.end.balls.len()
+            ));
+        }
+        for (b, (actual, expected)) in end.balls.iter().zip(&self.end.balls).enumerate() {
+            if actual != expected {
+                differences.push(format!(
+                    "ball {} ended up in {}, not {}",
+                    b, actual, expected
+                ));
+            }
+        }
+
+        if differences.is_empty() {
+            Ok(())
+        } else {
+            Err(differences.join("; "))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Fly around the default scene, under the frame and back.
+    fn session() -> Model {
+        let mut model = Model::new();
+        for _ in 0..7 {
+            model.update(0.0123);
+        }
+        model.key_down("KeyW");
+        model.key_down("ShiftLeft");
+        for _ in 0..100 {
+            model.update(0.031);
+        }
+        model.key_up("ShiftLeft");
+        model.look([-150., 20.]);
+        model.look([-150., 20.]);
+        model.key_down("KeyF");
+        for _ in 0..50 {
+            model.update(0.0071);
+        }
+        model.key_up("KeyW");
+        model.key_up("KeyF");
+        model
+    }
+
+    #[test]
+    fn playing_back() {
+        let model = session();
+        let replay = Replay::from_json(&model.replay().to_json()).unwrap();
+        assert_eq!(replay, model.replay());
+        replay.check().unwrap();
+
+        let played = replay.play().unwrap();
+        assert_eq!(played.snapshot(), model.snapshot());
+        // Playing a replay records it again.
+        assert_eq!(played.replay(), replay);
+    }
+
+    #[test]
+    fn replaying_until_the_end() {
+        // Nothing happens for a while after the last input.
+        let mut model = session();
+        for _ in 0..30 {
+            model.update(0.031);
+        }
+        let replay = model.replay();
+
+        let mut played = replay.start().unwrap();
+        for _ in 0..replay.ticks {
+            assert!(played.replaying());
+            played.tick();
+        }
+        played.flush();
+        assert!(!played.replaying());
+    }
+
+    #[test]
+    fn noticing_differences() {
+        let mut replay = session().replay();
+        replay.end.player.world += 1;
+        replay.end.balls[2].position[0] += 1e-6;
+        let error = replay.check().unwrap_err();
+        assert!(error.starts_with("the player ended up in world"));
+        assert!(error.contains("; ball 2 ended up in"));
+
+        replay.inputs.clear();
+        assert!(replay.check().is_err());
+    }
+
+    #[test]
+    fn combining_analog_input() {
+        let stick = Analog {
+            movement: [0.8, 0.],
+            look: [1., 0.],
+            vertical: -1.,
+        };
+        let joystick = Analog {
+            movement: [0.5, 0.5],
+            ..Analog::default()
+        };
+        // Neither zeroes the other.
+        assert_eq!(stick.combine(Analog::default()), stick);
+        assert_eq!(
+            stick.combine(joystick),
+            Analog {
+                movement: [1., 0.5],
+                look: [1., 0.],
+                vertical: -1.,
+            }
+        );
+    }
+}
@@ -0,0 +1,374 @@
+// Scenes, described in JSON, so that new ones can be made without recompiling.
+//
+// A scene says which knot frames the portal, how passing under its arcs permutes the worlds,
+// what colour each world's sky and ground are, and what else is in the worlds: static meshes, and balls moving along paths.
+// See `scenes/` for examples.
+
+use crate::expr::Expr;
+use crate::knot::{Knot, SHADER_VERTICES};
+use crate::modeling::{self, Triangle};
+use crate::portal::Portal;
+use crate::Ball;
+use serde::Deserialize;
+
+// The scene the game starts in.
+pub const DEFAULT: &str = include_str!("../../scenes/trefoil.json");
+
+type Color = [f32; 4];
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scene {
+    pub knot: KnotShape,
+    pub portal: Transitions,
+    // One colour for every world, or one per world. Left out, each world gets its own, from `modeling::world_colors`.
+    #[serde(default)]
+    pub sky: Option<Vec<Color>>,
+    #[serde(default = "default_ground")]
+    pub ground: Vec<Color>,
+    #[serde(default)]
+    pub meshes: Vec<Mesh>,
+    #[serde(default)]
+    pub balls: Vec<SceneBall>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KnotShape {
+    Trefoil,
+    FigureEight,
+    Torus { p: u32, q: u32 },
+    Hopf,
+    Borromean,
+    Whitehead,
+}
+
+// What passing under each arc does to the worlds.
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Transitions {
+    // See `Portal::fox`.
+    Fox,
+    // See `Portal::dihedral`.
+    Dihedral,
+    // One permutation of the worlds per arc, applied when passing under the arc from left to right. See `Portal::new`.
+    Permutations(Vec<Vec<i32>>),
+}
+
+// A triangle mesh, which looks the same in every world except for its colour.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Mesh {
+    pub vertices: Vec<[f32; 3]>,
+    // Indices into `vertices`, counterclockwise as seen from the front.
+    pub triangles: Vec<[usize; 3]>,
+    // One colour per world, like the sky.
+    pub colors: Vec<Color>,
+    #[serde(default = "default_ambient_factor")]
+    pub ambient_factor: f32,
+    #[serde(default = "default_diffuse_factor")]
+    pub diffuse_factor: f32,
+}
+
+// A ball either follows a `path`, or starts at `position` with `velocity` and moves freely. See `physics.rs`.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SceneBall {
+    pub color: Color,
+    // The world the ball starts in.
+    pub world: i32,
+    pub path: Option<Path>,
+    pub position: Option<[f32; 3]>,
+    pub velocity: Option<[f32; 3]>,
+}
+
+// A closed path, with period 2π, so that a ball following it keeps threading through the portal the same way.
+// Each coordinate is an expression in `t`, such as `"sin(t) + 2 * sin(2 * t)"`; see `expr.rs`.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Path {
+    pub x: Expr,
+    pub y: Expr,
+    pub z: Expr,
+}
+
+#[derive(Debug)]
+pub enum Error {
+    // The file isn't valid JSON, or doesn't have the shape of a scene.
+    Json(serde_json::Error),
+    // The file has the shape of a scene, but doesn't make sense as one.
+    Invalid(String),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Error::Json(e) => write!(f, "{}", e),
+            Error::Invalid(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Json(e)
+    }
+}
+
+impl Scene {
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    pub fn portal(&self) -> Result<Portal, Error> {
+        let knot = match self.knot {
+            KnotShape::Trefoil => Knot::trefoil(),
+            KnotShape::FigureEight => Knot::figure_eight(),
+            KnotShape::Torus { p, q } => {
+                if p == 0 || q == 0 || gcd(p, q) != 1 {
+                    return Err(Error::Invalid(format!(
+                        "a ({}, {}) torus knot would pass through itself",
+                        p, q
+                    )));
+                }
+                let knot = Knot::torus(p, q);
+                if knot.polyline(SHADER_VERTICES).is_none() {
+                    return Err(Error::Invalid(format!(
+                        "a ({}, {}) torus knot has too many crossings for the renderer",
+                        p, q
+                    )));
+                }
+                knot
+            }
+            KnotShape::Hopf => Knot::hopf(),
+            KnotShape::Borromean => Knot::borromean(),
+            KnotShape::Whitehead => Knot::whitehead(),
+        };
+
+        match &self.portal {
+            Transitions::Fox | Transitions::Dihedral if knot.fox_colouring().is_none() => Err(
+                Error::Invalid("this knot has no Fox colourings with a small prime".to_string()),
+            ),
+            Transitions::Fox => Ok(Portal::fox(knot)),
+            Transitions::Dihedral => Ok(Portal::dihedral(knot)),
+            Transitions::Permutations(permutations) => {
+                Portal::try_new(knot, permutations.clone()).map_err(Error::Invalid)
+            }
+        }
+    }
+
+    // The sky colour of each world.
+    pub fn sky(&self, portal: &Portal) -> Result<Vec<Color>, Error> {
+        match &self.sky {
+            None => Ok(modeling::world_colors(portal.worlds())),
+            Some(sky) => palette(sky, portal, "sky"),
+        }
+    }
+
+    // The knot's tube, the sky, the ground and the meshes.
+    pub fn static_geometry(&self, portal: &Portal) -> Result<Vec<Triangle>, Error> {
+        let sky = self.sky(portal)?;
+        let ground = palette(&self.ground, portal, "ground")?;
+
+        let mut out: Vec<Triangle> = modeling::knot(portal, &sky)
+            .chain(modeling::skybox(sky.into()))
+            .chain(modeling::ground(ground.into()))
+            .collect();
+
+        for (m, mesh) in self.meshes.iter().enumerate() {
+            let colors: modeling::Palette =
+                palette(&mesh.colors, portal, &format!("mesh {}", m))?.into();
+            for triangle in &mesh.triangles {
+                let mut vertices = [nalgebra::Vector3::zeros(); 3];
+                for (v, &i) in vertices.iter_mut().zip(triangle) {
+                    let [x, y, z] = *mesh.vertices.get(i).ok_or_else(|| {
+                        Error::Invalid(format!(
+                            "mesh {} has {} vertices, but a triangle uses vertex {}",
+                            m,
+                            mesh.vertices.len(),
+                            i
+                        ))
+                    })?;
+                    *v = nalgebra::Vector3::new(x, y, z);
+                }
+                out.push(Triangle {
+                    vertices,
+                    center: None,
+                    colors: colors.clone(),
+                    ambient_factor: mesh.ambient_factor,
+                    diffuse_factor: mesh.diffuse_factor,
+                });
+            }
+        }
+
+        Ok(out)
+    }
+
+    pub fn balls(&self, portal: &Portal) -> Result<Vec<Ball>, Error> {
+        self.balls
+            .iter()
+            .enumerate()
+            .map(|(b, ball)| {
+                if !(0..portal.worlds()).contains(&ball.world) {
+                    return Err(Error::Invalid(format!(
+                        "ball {} starts in world {}, but there are only {} worlds",
+                        b,
+                        ball.world,
+                        portal.worlds()
+                    )));
+                }
+                match (&ball.path, ball.position) {
+                    (Some(path), None) if ball.velocity.is_none() => {
+                        let [x, y, z] = [path.x.clone(), path.y.clone(), path.z.clone()];
+                        Ok(Ball::new(ball.color, ball.world, move |t| {
+                            let t = f64::from(t);
+                            nalgebra::Vector3::new(
+                                x.eval(t) as f32,
+                                y.eval(t) as f32,
+                                z.eval(t) as f32,
+                            )
+                        }))
+                    }
+                    (None, Some(position)) => Ok(Ball::free(
+                        ball.color,
+                        ball.world,
+                        position.into(),
+                        ball.velocity.unwrap_or_default().into(),
+                    )),
+                    _ => Err(Error::Invalid(format!(
+                        "ball {} needs either a `path`, or a `position` and maybe a `velocity`",
+                        b
+                    ))),
+                }
+            })
+            .collect()
+    }
+}
+
+// A colour for each world, from either one colour for all of them, or one per world.
+fn palette(colors: &[Color], portal: &Portal, what: &str) -> Result<Vec<Color>, Error> {
+    let worlds = portal.worlds() as usize;
+    match colors.len() {
+        1 => Ok(vec![colors[0]; worlds]),
+        n if n == worlds => Ok(colors.to_vec()),
+        n => Err(Error::Invalid(format!(
+            "the {} needs one colour, or one for each of the {} worlds, not {}",
+            what, worlds, n
+        ))),
+    }
+}
+
+fn gcd(a: u32, b: u32) -> u32 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+fn default_ground() -> Vec<Color> {
+    vec![modeling::GROUND_COLOR]
+}
+
+fn default_ambient_factor() -> f32 {
+    0.2
+}
+
+fn default_diffuse_factor() -> f32 {
+    0.8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_scene_is_the_demo() {
+        let scene = Scene::from_json(DEFAULT).unwrap();
+        let portal = scene.portal().unwrap();
+        let demo = Portal::trefoil();
+        assert_eq!(portal.worlds(), demo.worlds());
+        for arc in 0..portal.knot.arcs() {
+            assert_eq!(portal.permutation(arc), demo.permutation(arc));
+        }
+        assert_eq!(scene.balls(&portal).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn example_scenes() {
+        for json in &[
+            DEFAULT,
+            include_str!("../../scenes/borromean.json"),
+            include_str!("../../scenes/physics.json"),
+        ] {
+            let scene = Scene::from_json(json).unwrap();
+            let portal = scene.portal().unwrap();
+            scene.static_geometry(&portal).unwrap();
+            scene.balls(&portal).unwrap();
+        }
+    }
+
+    fn error(json: &str) -> String {
+        let scene = match Scene::from_json(json) {
+            Ok(scene) => scene,
+            Err(e) => return e.to_string(),
+        };
+        let result = scene
+            .portal()
+            .and_then(|portal| scene.static_geometry(&portal).and(scene.balls(&portal)));
+        match result {
+            Ok(_) => panic!("no error"),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    #[test]
+    fn errors() {
+        assert!(error(r#"{ "knot": "trefoil", "portal": "fox", }"#).contains("line 1"));
+        assert!(error(r#"{ "knot": "unknot", "portal": "fox" }"#).contains("unknown variant"));
+        assert!(
+            error(r#"{ "knot": { "torus": { "p": 2, "q": 4 } }, "portal": "fox" }"#)
+                .contains("pass through itself")
+        );
+        assert!(
+            error(r#"{ "knot": { "torus": { "p": 13, "q": 14 } }, "portal": "fox" }"#)
+                .contains("too many crossings")
+        );
+        assert!(error(
+            r#"{ "knot": "trefoil", "portal": { "permutations": [[0, 1], [1, 0], [1, 0]] } }"#
+        )
+        .contains("inconsistent"));
+        assert!(error(r#"{ "knot": "trefoil", "portal": "fox", "sky": [] }"#).contains("sky"));
+        assert!(error(
+            r#"{ "knot": "trefoil", "portal": "fox", "sky": [[1, 1, 1, 1], [0, 0, 0, 1]] }"#
+        )
+        .contains("one for each of the 3 worlds, not 2"));
+        assert!(error(
+            r#"{ "knot": "trefoil", "portal": "fox", "balls": [
+                { "color": [1, 1, 1, 1], "world": 3, "path": { "x": "0", "y": "0", "z": "0" } }
+            ] }"#
+        )
+        .contains("only 3 worlds"));
+        assert!(error(
+            r#"{ "knot": "trefoil", "portal": "fox", "balls": [
+                { "color": [1, 1, 1, 1], "world": 0, "position": [0, 0, 0],
+                  "path": { "x": "0", "y": "0", "z": "0" } }
+            ] }"#
+        )
+        .contains("either a `path`, or a `position`"));
+        assert!(error(
+            r#"{ "knot": "trefoil", "portal": "fox", "meshes": [
+                { "vertices": [[0, 0, 0]], "triangles": [[0, 0, 1]], "colors": [[1, 1, 1, 1]] }
+            ] }"#
+        )
+        .contains("vertex 1"));
+        assert!(error(
+            r#"{ "knot": "trefoil", "portal": "fox", "balls": [
+                { "color": [1, 1, 1, 1], "world": 0, "path": { "x": "sin(t", "y": "0", "z": "0" } }
+            ] }"#
+        )
+        .contains("in `sin(t`, at column 6: expected `)` to match the `(` at column 4 at line 2"));
+    }
+}
@@ -0,0 +1,134 @@
+// What the player can change about the controls and the view.
+//
+// Keys are named by `KeyboardEvent.code`, which names the key's position rather than what's printed on it,
+// so that WASD is in the same place on AZERTY and Dvorak keyboards.
+// Gamepad buttons are keys too, named by their index in the standard mapping: `Gamepad0` is the bottom face button. See `gamepad.rs`.
+// So are the on-screen buttons for touch screens, `TouchUp` and `TouchDown`. See `touch.rs`.
+// The web front-end keeps the settings in `localStorage`, as JSON; any field left out has its default.
+
+use crate::gamepad::GamepadSettings;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    pub bindings: Bindings,
+    // Radians per pixel of mouse movement.
+    pub sensitivity: f32,
+    pub invert_y: bool,
+    // The vertical field of view, in degrees.
+    pub fov: f32,
+    // Units per second, when flying or walking. Sprinting is faster.
+    pub speed: f32,
+    pub gamepad: GamepadSettings,
+}
+
+// The keys for each action. Holding any of them does the action.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Bindings {
+    pub forward: Vec<String>,
+    pub back: Vec<String>,
+    pub left: Vec<String>,
+    pub right: Vec<String>,
+    // Flying up, or jumping.
+    pub up: Vec<String>,
+    // Flying down, or sprinting.
+    pub down: Vec<String>,
+    pub toggle_mode: Vec<String>,
+
+    // These are the front-end's, not the game's, so they aren't recorded in replays, and work while one plays.
+    pub save_replay: Vec<String>,
+    // The HUD and the minimap are only in the web front-end.
+    pub toggle_hud: Vec<String>,
+    pub toggle_minimap: Vec<String>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            bindings: Bindings::default(),
+            sensitivity: 3e-3,
+            invert_y: false,
+            fov: 90.,
+            speed: 0.5,
+            gamepad: GamepadSettings::default(),
+        }
+    }
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        let keys = |keys: &[&str]| keys.iter().map(|k| k.to_string()).collect();
+        Self {
+            // The gamepad's buttons are the d-pad, and the bottom, right and top face buttons.
+            forward: keys(&["KeyW", "ArrowUp", "Gamepad12"]),
+            back: keys(&["KeyS", "ArrowDown", "Gamepad13"]),
+            left: keys(&["KeyA", "ArrowLeft", "Gamepad14"]),
+            right: keys(&["KeyD", "ArrowRight", "Gamepad15"]),
+            up: keys(&["Space", "Gamepad0", "TouchUp"]),
+            down: keys(&["ShiftLeft", "ShiftRight", "Gamepad1", "TouchDown"]),
+            toggle_mode: keys(&["KeyF", "Gamepad3"]),
+
+            save_replay: keys(&["F8"]),
+            toggle_hud: keys(&["F3"]),
+            toggle_minimap: keys(&["KeyM"]),
+        }
+    }
+}
+
+impl Bindings {
+    /// Whether `key` does anything.
+    pub fn contains(&self, key: &str) -> bool {
+        [
+            &self.forward,
+            &self.back,
+            &self.left,
+            &self.right,
+            &self.up,
+            &self.down,
+            &self.toggle_mode,
+            &self.save_replay,
+            &self.toggle_hud,
+            &self.toggle_minimap,
+        ]
+        .iter()
+        .any(|keys| keys.iter().any(|k| k == key))
+    }
+}
+
+impl Settings {
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_fields_have_defaults() {
+        let settings =
+            Settings::from_json(r#"{ "invert_y": true, "bindings": { "forward": ["KeyZ"] } }"#)
+                .unwrap();
+        assert!(settings.invert_y);
+        assert_eq!(settings.bindings.forward, ["KeyZ"]);
+        assert_eq!(settings.bindings.back, Bindings::default().back);
+        assert_eq!(settings.bindings.save_replay, ["F8"]);
+        assert_eq!(settings.fov, 90.);
+        assert!(settings.bindings.contains("KeyZ"));
+        assert!(!settings.bindings.contains("KeyW"));
+        assert!(settings.bindings.contains("F3"));
+
+        assert_eq!(
+            Settings::from_json(&Settings::default().to_json()).unwrap(),
+            Settings::default()
+        );
+        assert!(Settings::from_json(r#"{ "sensitivty": 1 }"#).is_err());
+    }
+}
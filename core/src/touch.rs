@@ -0,0 +1,150 @@
+// Touch screens, which have no keyboard, and no mouse to capture.
+//
+// A finger put down on the left half of the screen is a virtual joystick: dragging it away from where it started moves,
+// like the gamepad's left stick. A finger dragged on the right half looks around, like the mouse.
+// The front-end draws the joystick where `Touches::joystick` says, and its own buttons for up and down,
+// which are the keys `TouchUp` and `TouchDown`.
+// Positions are in CSS pixels, from the top left of the screen.
+
+use crate::replay::{Analog, Input};
+
+// Dragging the joystick this far is full speed.
+pub const JOYSTICK_RADIUS: f32 = 60.;
+
+#[derive(Default)]
+pub struct Touches {
+    // The finger on the joystick, where it started, and where it is.
+    joystick: Option<(i32, [f32; 2], [f32; 2])>,
+    // The finger looking around, and where it was last.
+    look: Option<(i32, [f32; 2])>,
+}
+
+impl Touches {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A finger touched the screen, which is `width` wide.
+    pub fn start(&mut self, id: i32, pos: [f32; 2], width: f32) -> Vec<Input> {
+        if pos[0] < width / 2. {
+            if self.joystick.is_none() {
+                self.joystick = Some((id, pos, pos));
+            }
+        } else if self.look.is_none() {
+            self.look = Some((id, pos));
+        }
+        Vec::new()
+    }
+
+    pub fn moved(&mut self, id: i32, pos: [f32; 2]) -> Vec<Input> {
+        match (&mut self.joystick, &mut self.look) {
+            (Some((finger, _, current)), _) if *finger == id => {
+                *current = pos;
+                vec![Input::Analog(self.analog())]
+            }
+            (_, Some((finger, last))) if *finger == id => {
+                let movement = [pos[0] - last[0], pos[1] - last[1]];
+                *last = pos;
+                vec![Input::Look(movement)]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// A finger left the screen, or the touch was cancelled.
+    pub fn end(&mut self, id: i32) -> Vec<Input> {
+        if self.joystick.map(|(finger, ..)| finger) == Some(id) {
+            self.joystick = None;
+            return vec![Input::Analog(Analog::default())];
+        }
+        if self.look.map(|(finger, _)| finger) == Some(id) {
+            self.look = None;
+        }
+        Vec::new()
+    }
+
+    /// Where to draw the joystick, while it's held: its centre, and its knob.
+    pub fn joystick(&self) -> Option<([f32; 2], [f32; 2])> {
+        let (_, start, _) = self.joystick?;
+        let [right, forward] = self.analog().movement;
+        Some((
+            start,
+            [
+                start[0] + right * JOYSTICK_RADIUS,
+                start[1] - forward * JOYSTICK_RADIUS,
+            ],
+        ))
+    }
+
+    fn analog(&self) -> Analog {
+        let (_, start, current) = match self.joystick {
+            Some(joystick) => joystick,
+            None => return Analog::default(),
+        };
+        let [x, y] = [current[0] - start[0], current[1] - start[1]];
+        let scale = 1. / JOYSTICK_RADIUS.max((x * x + y * y).sqrt());
+        Analog {
+            movement: [x * scale, -y * scale],
+            ..Analog::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WIDTH: f32 = 400.;
+
+    #[test]
+    fn joystick() {
+        let mut touches = Touches::new();
+        assert_eq!(touches.start(1, [100., 500.], WIDTH), []);
+        assert_eq!(
+            touches.moved(1, [100., 470.]),
+            [Input::Analog(Analog {
+                movement: [0., 0.5],
+                ..Analog::default()
+            })]
+        );
+
+        // Past the edge of the joystick, it's full speed.
+        touches.moved(1, [400., 500.]);
+        assert_eq!(touches.analog().movement, [1., 0.]);
+        assert_eq!(touches.joystick(), Some(([100., 500.], [160., 500.])));
+
+        assert_eq!(touches.end(1), [Input::Analog(Analog::default())]);
+        assert_eq!(touches.joystick(), None);
+    }
+
+    #[test]
+    fn looking() {
+        let mut touches = Touches::new();
+        touches.start(7, [300., 200.], WIDTH);
+        assert_eq!(touches.moved(7, [310., 195.]), [Input::Look([10., -5.])]);
+        assert_eq!(touches.moved(7, [311., 195.]), [Input::Look([1., 0.])]);
+        assert_eq!(touches.end(7), []);
+        assert_eq!(touches.moved(7, [320., 195.]), []);
+    }
+
+    #[test]
+    fn both_at_once() {
+        let mut touches = Touches::new();
+        touches.start(1, [100., 500.], WIDTH);
+        touches.start(2, [300., 500.], WIDTH);
+        // A second finger on the left doesn't take over the joystick.
+        touches.start(3, [50., 100.], WIDTH);
+        assert_eq!(touches.moved(3, [60., 100.]), []);
+
+        assert_eq!(touches.moved(2, [290., 500.]), [Input::Look([-10., 0.])]);
+        assert_eq!(
+            touches.moved(1, [130., 500.]),
+            [Input::Analog(Analog {
+                movement: [0.5, 0.],
+                ..Analog::default()
+            })]
+        );
+        touches.end(2);
+        assert_eq!(touches.analog().movement, [0.5, 0.]);
+    }
+}
@@ -0,0 +1,65 @@
+//! Replays the demo scene's balls for many periods.
+//!
+//! Every ball path is a closed loop with period 2π, so each time a ball returns to its starting point,
+//! its world is transformed by the same permutation. The sequence of worlds it has at those moments must therefore be periodic.
+
+use knotted_portal_core::model::Model;
+use knotted_portal_core::portal::Portal;
+use knotted_portal_core::Ball;
+
+const PERIODS: usize = 3000;
+
+/// Frame times to replay at. At 144Hz, the old f32 quartic solver glitched ball 1 after about 800 periods.
+const FRAME_TIMES: [f64; 4] = [1. / 30., 1. / 60., 1. / 144., 0.0173];
+
+#[test]
+fn ball_worlds_are_periodic() {
+    for &dt in FRAME_TIMES.iter() {
+        let model = Model::new();
+        for (i, ball) in model.balls.into_iter().enumerate() {
+            check_periodic(&model.portal, dt, i, ball);
+        }
+    }
+}
+
+fn check_periodic(portal: &Portal, dt: f64, i: usize, mut ball: Ball) {
+    let mut worlds = vec![ball.world];
+
+    let mut t = 0f64;
+    let mut period = 0;
+    while worlds.len() <= PERIODS {
+        // Accumulate time in f64, so that the ball's position doesn't drift over thousands of periods.
+        let next_t = t + dt;
+        ball.travel(portal, (next_t as f32) - ball.t);
+        t = next_t;
+
+        let next_period = (t / std::f64::consts::TAU) as usize;
+        if next_period != period {
+            period = next_period;
+            worlds.push(ball.world);
+        }
+    }
+
+    let cycle = (1..=6)
+        .find(|&n| worlds[n] == worlds[0])
+        .unwrap_or_else(|| {
+            panic!(
+                "ball {} (dt = {}) never returned to its starting world: {:?}",
+                i,
+                dt,
+                &worlds[..7]
+            )
+        });
+
+    for (k, pair) in worlds.iter().zip(&worlds[cycle..]).enumerate() {
+        assert_eq!(
+            pair.0,
+            pair.1,
+            "ball {} (dt = {}) glitched between periods {} and {}",
+            i,
+            dt,
+            k,
+            k + cycle
+        );
+    }
+}
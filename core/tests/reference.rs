@@ -0,0 +1,125 @@
+//! Compares `Portal::travel` against the slow reference implementation, on random paths.
+
+use knotted_portal_core::knot;
+use knotted_portal_core::portal::{self, Portal};
+use nalgebra::Vector3;
+
+// xorshift64*, so that failures are reproducible without pulling in a dependency.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> f32 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        (self.0.wrapping_mul(0x2545_f491_4f6c_dd1d) >> 40) as f32 / (1 << 24) as f32
+    }
+
+    fn range(&mut self, lo: f32, hi: f32) -> f32 {
+        lo + (hi - lo) * self.next()
+    }
+
+    fn point(&mut self) -> Vector3<f32> {
+        Vector3::new(
+            self.range(-4., 4.),
+            self.range(-4., 4.),
+            self.range(-2., 2.),
+        )
+    }
+}
+
+// Near the knot itself, the reference implementation's polyline and the real knot disagree.
+struct KnotSamples(Vec<Vector3<f32>>);
+
+impl KnotSamples {
+    fn new() -> Self {
+        let knot = knot::Knot::trefoil();
+        Self(
+            (0..2048)
+                .map(|i| nalgebra::convert(knot.point(i as f64 * std::f64::consts::TAU / 2048.)))
+                .collect(),
+        )
+    }
+
+    fn near(&self, a: Vector3<f32>, b: Vector3<f32>) -> bool {
+        let ab = b - a;
+        self.0.iter().any(|&p| {
+            let s = ((p - a).dot(&ab) / ab.norm_squared()).clamp(0., 1.);
+            (a + s * ab - p).norm_squared() < 0.02 * 0.02
+        })
+    }
+}
+
+fn compare_segments(seed: u64, count: usize) {
+    let portal = Portal::trefoil();
+    let knot = KnotSamples::new();
+    let mut rng = Rng(seed);
+    let mut compared = 0;
+    while compared < count {
+        let (a, b) = (rng.point(), rng.point());
+        if knot.near(a, b) {
+            continue;
+        }
+        compared += 1;
+
+        let start_world = (rng.next() * 6.) as i32;
+        let mut world = start_world;
+        portal.travel(&mut world, a, b);
+        assert_eq!(
+            world,
+            portal::reference::travel_path(&[a, b], start_world),
+            "travel from {:?} to {:?} in world {}",
+            a,
+            b,
+            start_world
+        );
+    }
+}
+
+#[test]
+fn random_segments() {
+    compare_segments(1, 10_000);
+}
+
+#[test]
+#[ignore = "slow; run with `cargo test --release -- --ignored`"]
+fn millions_of_random_segments() {
+    compare_segments(2, 2_000_000);
+}
+
+#[test]
+fn random_polylines() {
+    let portal = Portal::trefoil();
+    let knot = KnotSamples::new();
+    let mut rng = Rng(3);
+    for _ in 0..500 {
+        // A short random walk, like the player's path over a few seconds.
+        let mut path = vec![rng.point()];
+        for _ in 0..50 {
+            let last = path[path.len() - 1];
+            path.push(last + 0.2 * rng.point());
+        }
+        if path.windows(2).any(|w| knot.near(w[0], w[1])) {
+            continue;
+        }
+
+        let mut world = 0;
+        for w in path.windows(2) {
+            portal.travel(&mut world, w[0], w[1]);
+        }
+        assert_eq!(world, portal::reference::travel_path(&path, 0));
+    }
+}
+
+#[test]
+fn just_under_a_crossing() {
+    let portal = Portal::trefoil();
+    // Passes about 3e-5 from one of the diagram's crossings, under both strands.
+    let a = Vector3::new(-2.489_260_2, 3.687_811_9, -0.358_106_37);
+    let b = Vector3::new(1.879_311_6, -1.429_767_6, -1.723_577_3);
+    for start_world in 0..6 {
+        let mut world = start_world;
+        portal.travel(&mut world, a, b);
+        assert_eq!(world, portal::reference::travel_path(&[a, b], start_world));
+    }
+}
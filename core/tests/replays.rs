@@ -0,0 +1,26 @@
+//! Plays every replay in `replays/`, checking that the player and the balls still end up where they did when it was recorded.
+//!
+//! To add one, record it in either front-end, and save it there with a name saying what it checks.
+
+use knotted_portal_core::replay::Replay;
+use std::path::Path;
+
+#[test]
+fn replays_end_where_they_did() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../replays");
+    let mut count = 0;
+    for entry in std::fs::read_dir(&dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension() != Some("json".as_ref()) {
+            continue;
+        }
+        let json = std::fs::read_to_string(&path).unwrap();
+        let replay =
+            Replay::from_json(&json).unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+        if let Err(e) = replay.check() {
+            panic!("{}: {}", path.display(), e);
+        }
+        count += 1;
+    }
+    assert!(count > 0, "no replays in {}", dir.display());
+}
@@ -0,0 +1,196 @@
+#![forbid(unsafe_code)]
+
+// A desktop viewer, which runs the game without a browser or a GPU.
+//
+// It drives the same `Model` as the web front-end, and draws it with the software renderer.
+// Click the window to capture the mouse, and press Escape to release it.
+// Press F8, or whatever `save_replay` is bound to, to save a replay of the game so far, as `replay-SECONDS.json` in the current directory.
+//
+// Usage: `knotted-portal [SCENE.json]`. Without a scene file, it plays the default scene.
+// Or `knotted-portal --replay REPLAY.json`, to watch a replay.
+
+use knotted_portal_core::model::Model;
+use knotted_portal_core::replay::Replay;
+use knotted_portal_raster::Renderer;
+use std::num::NonZeroU32;
+use std::rc::Rc;
+use std::time::Instant;
+use winit::dpi::LogicalSize;
+use winit::event::{DeviceEvent, ElementState, Event, KeyEvent, MouseButton, WindowEvent};
+use winit::event_loop::{ControlFlow, EventLoop};
+use winit::keyboard::{KeyCode, PhysicalKey};
+use winit::window::{CursorGrabMode, Window, WindowBuilder};
+
+// Software rendering is slow, so draw the scene at a lower resolution, with each of its pixels covering a square of this many screen pixels.
+const PIXEL_SIZE: u32 = 4;
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let loaded = match &args[..] {
+        [] => Ok(Model::new()),
+        [flag, path] if flag == "--replay" => load_replay(path),
+        [path] => load(path),
+        _ => {
+            eprintln!("usage: knotted-portal [SCENE.json | --replay REPLAY.json]");
+            std::process::exit(2);
+        }
+    };
+    let mut model = match loaded {
+        Ok(model) => model,
+        Err(e) => {
+            eprintln!("{}: {}", args.last().unwrap(), e);
+            std::process::exit(1);
+        }
+    };
+
+    let event_loop = EventLoop::new().unwrap();
+    let window = Rc::new(
+        WindowBuilder::new()
+            .with_title("Knotted Portal")
+            .with_inner_size(LogicalSize::new(800, 600))
+            .build(&event_loop)
+            .unwrap(),
+    );
+    let context = softbuffer::Context::new(window.clone()).unwrap();
+    let mut surface = softbuffer::Surface::new(&context, window.clone()).unwrap();
+
+    let renderer = Renderer::new(model.static_geometry());
+
+    let mut grabbed = false;
+    let mut last_frame = Instant::now();
+
+    event_loop.set_control_flow(ControlFlow::Poll);
+    event_loop
+        .run(move |event, elwt| match event {
+            Event::WindowEvent { event, .. } => match event {
+                WindowEvent::CloseRequested => elwt.exit(),
+                WindowEvent::MouseInput {
+                    state: ElementState::Pressed,
+                    button: MouseButton::Left,
+                    ..
+                } => grabbed = grab(&window, true),
+                WindowEvent::Focused(false) => {
+                    grabbed = grab(&window, false);
+                    if !model.replaying() {
+                        model.release_keys();
+                    }
+                }
+                WindowEvent::KeyboardInput {
+                    event:
+                        KeyEvent {
+                            physical_key,
+                            state,
+                            ..
+                        },
+                    ..
+                } => {
+                    // winit names its key codes after `KeyboardEvent.code`, which is what `Model` expects.
+                    let key = match physical_key {
+                        PhysicalKey::Code(code) => format!("{:?}", code),
+                        PhysicalKey::Unidentified(_) => return,
+                    };
+                    if physical_key == PhysicalKey::Code(KeyCode::Escape) {
+                        grabbed = grab(&window, false);
+                    } else if model.settings.bindings.save_replay.contains(&key) {
+                        if state == ElementState::Pressed {
+                            save_replay(&model);
+                        }
+                    } else if model.replaying() {
+                        // Watching a replay.
+                    } else {
+                        match state {
+                            ElementState::Pressed => model.key_down(&key),
+                            ElementState::Released => model.key_up(&key),
+                        }
+                    }
+                }
+                WindowEvent::RedrawRequested => {
+                    let now = Instant::now();
+                    let dt = now - last_frame;
+                    last_frame = now;
+
+                    model.update(dt.as_secs_f32());
+                    draw(&window, &mut surface, &renderer, &model);
+                }
+                _ => {}
+            },
+            Event::DeviceEvent {
+                event: DeviceEvent::MouseMotion { delta: (x, y) },
+                ..
+            } if grabbed && !model.replaying() => model.look([x as f32, y as f32]),
+            Event::AboutToWait => window.request_redraw(),
+            _ => {}
+        })
+        .unwrap();
+}
+
+fn load(path: &str) -> Result<Model, Box<dyn std::error::Error>> {
+    let json = std::fs::read_to_string(path)?;
+    Ok(Model::from_json(&json)?)
+}
+
+fn load_replay(path: &str) -> Result<Model, Box<dyn std::error::Error>> {
+    let json = std::fs::read_to_string(path)?;
+    Ok(Replay::from_json(&json)?.start()?)
+}
+
+fn save_replay(model: &Model) {
+    let seconds = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let path = format!("replay-{}.json", seconds);
+    match std::fs::write(&path, model.replay().to_json()) {
+        Ok(()) => println!("saved {}", path),
+        Err(e) => eprintln!("{}: {}", path, e),
+    }
+}
+
+// Capture or release the mouse, returning whether it is now captured.
+fn grab(window: &Window, grab: bool) -> bool {
+    if !grab {
+        let _ = window.set_cursor_grab(CursorGrabMode::None);
+        window.set_cursor_visible(true);
+        return false;
+    }
+
+    // Some platforms can only lock the cursor in place, and others can only confine it to the window.
+    let grabbed = window
+        .set_cursor_grab(CursorGrabMode::Locked)
+        .or_else(|_| window.set_cursor_grab(CursorGrabMode::Confined))
+        .is_ok();
+    window.set_cursor_visible(!grabbed);
+    grabbed
+}
+
+fn draw(
+    window: &Rc<Window>,
+    surface: &mut softbuffer::Surface<Rc<Window>, Rc<Window>>,
+    renderer: &Renderer,
+    model: &Model,
+) {
+    let size = window.inner_size();
+    let (width, height) = match (NonZeroU32::new(size.width), NonZeroU32::new(size.height)) {
+        (Some(width), Some(height)) => (width, height),
+        // Minimized.
+        _ => return,
+    };
+    surface.resize(width, height).unwrap();
+
+    let image = renderer.render(
+        &model.portal,
+        model.uniforms(),
+        model.dynamic_geometry(),
+        width.get().div_ceil(PIXEL_SIZE) as usize,
+        height.get().div_ceil(PIXEL_SIZE) as usize,
+    );
+
+    let mut buffer = surface.buffer_mut().unwrap();
+    for y in 0..height.get() {
+        for x in 0..width.get() {
+            let [r, g, b, _] = image.pixel((x / PIXEL_SIZE) as usize, (y / PIXEL_SIZE) as usize);
+            buffer[(y * width.get() + x) as usize] =
+                u32::from(b) | u32::from(g) << 8 | u32::from(r) << 16;
+        }
+    }
+    buffer.present().unwrap();
+}
@@ -0,0 +1,319 @@
+#![forbid(unsafe_code)]
+
+// A software renderer, for drawing frames without a GPU.
+//
+// It follows `render.rs` and the shaders as closely as it can: the same projection, back-face culling, depth test and blending.
+// Each pixel is coloured the way `fragment.glsl` colours it, by travelling from the eye to the pixel, and from there to the centre of its triangle.
+// The one difference is that it travels with `Portal::travel`, on the knot itself, rather than on the shader's polyline.
+
+use knotted_portal_core::modeling::{Triangle, Uniforms};
+use knotted_portal_core::portal::Portal;
+use nalgebra::{Vector3, Vector4};
+
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    // Bottom row first, as in OpenGL.
+    pixels: Vec<[f32; 4]>,
+}
+
+impl Image {
+    fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![[0., 0., 0., 1.]; width * height],
+        }
+    }
+
+    // The pixel in column `x`, and row `y` counting from the top.
+    pub fn pixel(&self, x: usize, y: usize) -> [u8; 4] {
+        let [r, g, b, a] = self.pixels[(self.height - 1 - y) * self.width + x];
+        [to_u8(r), to_u8(g), to_u8(b), to_u8(a)]
+    }
+
+    // RGBA, top row first.
+    pub fn to_rgba8(&self) -> Vec<u8> {
+        (0..self.height)
+            .flat_map(|y| (0..self.width).flat_map(move |x| self.pixel(x, y).to_vec()))
+            .collect()
+    }
+
+    pub fn write_png(&self, w: impl std::io::Write) -> Result<(), png::EncodingError> {
+        let mut encoder = png::Encoder::new(w, self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.write_header()?.write_image_data(&self.to_rgba8())
+    }
+
+    pub fn save(&self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+        let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        Ok(self.write_png(file)?)
+    }
+}
+
+fn to_u8(x: f32) -> u8 {
+    (x.clamp(0., 1.) * 255.).round() as u8
+}
+
+pub struct Renderer {
+    static_geometry: Vec<Triangle>,
+}
+
+impl Renderer {
+    pub fn new(static_geometry: impl IntoIterator<Item = Triangle>) -> Self {
+        Self {
+            static_geometry: static_geometry.into_iter().collect(),
+        }
+    }
+
+    pub fn render(
+        &self,
+        portal: &Portal,
+        uniforms: Uniforms,
+        mut dynamic_geometry: Vec<Triangle>,
+        width: usize,
+        height: usize,
+    ) -> Image {
+        let projection_matrix: nalgebra::Matrix4<f32> = nalgebra::Matrix4::new_perspective(
+            width as f32 / height as f32,
+            uniforms.fov,
+            0.01,
+            200.,
+        );
+        let mat: nalgebra::Matrix4<f32> =
+            projection_matrix * uniforms.player_isometry.inverse().to_homogeneous();
+
+        dynamic_geometry.sort_by_key(|tri| {
+            std::cmp::Reverse(
+                // farthest first
+                (tri.center() - uniforms.player_isometry.translation.vector)
+                    .norm_squared()
+                    .to_bits(), // to_bits is monotonic on positive floats, so this is an easy way to ignore NaN.
+            )
+        });
+
+        let mut image = Image::new(width, height);
+        let mut depth = vec![1.; width * height];
+
+        // Every pixel is independent of the others, so split the image into bands of rows, and draw each band on its own thread.
+        let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+        let rows_per_band = height.div_ceil(threads).max(1);
+        let band_size = (rows_per_band * width).max(1);
+        std::thread::scope(|scope| {
+            for (band, (pixels, depth)) in image
+                .pixels
+                .chunks_mut(band_size)
+                .zip(depth.chunks_mut(band_size))
+                .enumerate()
+            {
+                let mut target = Target {
+                    portal,
+                    uniforms: &uniforms,
+                    mat,
+                    width,
+                    height,
+                    rows: band * rows_per_band..band * rows_per_band + pixels.len() / width,
+                    pixels,
+                    depth,
+                };
+                let static_geometry = &self.static_geometry;
+                let dynamic_geometry = &dynamic_geometry;
+                scope.spawn(move || {
+                    for tri in static_geometry.iter().chain(dynamic_geometry) {
+                        target.draw(tri);
+                    }
+                });
+            }
+        });
+
+        image
+    }
+}
+
+// The part of the image one thread draws: rows `rows` of a `width` by `height` image.
+struct Target<'a> {
+    portal: &'a Portal,
+    uniforms: &'a Uniforms,
+    mat: nalgebra::Matrix4<f32>,
+    width: usize,
+    height: usize,
+    rows: std::ops::Range<usize>,
+    pixels: &'a mut [[f32; 4]],
+    depth: &'a mut [f32],
+}
+
+// A vertex after the vertex shader: its clip-space position, and its position in the world.
+#[derive(Clone, Copy)]
+struct Vertex {
+    clip: Vector4<f32>,
+    pos: Vector3<f32>,
+}
+
+impl Target<'_> {
+    fn draw(&mut self, tri: &Triangle) {
+        let vertices: Vec<Vertex> = tri
+            .vertices
+            .iter()
+            .map(|&pos| Vertex {
+                clip: self.mat * pos.push(1.),
+                pos,
+            })
+            .collect();
+
+        // Clip against the near and far planes. The rest of the view frustum is handled by only visiting pixels in the image.
+        let polygon = clip(vertices, |v| v.clip.z + v.clip.w);
+        let polygon = clip(polygon, |v| v.clip.w - v.clip.z);
+
+        for i in 1..polygon.len().saturating_sub(1) {
+            self.rasterize(tri, [polygon[0], polygon[i], polygon[i + 1]]);
+        }
+    }
+
+    fn rasterize(&mut self, tri: &Triangle, vertices: [Vertex; 3]) {
+        let (width, height) = (self.width as f64, self.height as f64);
+
+        // Window coordinates, with y up, in fixed point with `SUBPIXELS` steps per pixel, as GPUs do.
+        // That way, neighbouring triangles agree exactly on which pixels their shared edge covers, and there are no cracks between them.
+        const SUBPIXELS: f64 = 256.;
+        let window = vertices.map(|v| {
+            let clip: Vector4<f64> = nalgebra::convert(v.clip);
+            let ndc = clip.xyz() / clip.w;
+            [
+                ((ndc.x + 1.) / 2. * width * SUBPIXELS).round() as i128,
+                ((ndc.y + 1.) / 2. * height * SUBPIXELS).round() as i128,
+            ]
+        });
+        // Depth in 0..1.
+        let depths = vertices.map(|v| (v.clip.z / v.clip.w + 1.) / 2.);
+
+        let edge = |a: [i128; 2], b: [i128; 2], p: [i128; 2]| {
+            (b[0] - a[0]) * (p[1] - a[1]) - (b[1] - a[1]) * (p[0] - a[0])
+        };
+
+        // Counterclockwise triangles face the camera. Cull the rest.
+        let area = edge(window[0], window[1], window[2]);
+        if area <= 0 {
+            return;
+        }
+
+        // A pixel on the shared edge of two triangles belongs to just one of them: the one it's above or to the right of.
+        let top_left = |a: [i128; 2], b: [i128; 2]| b[1] < a[1] || (b[1] == a[1] && b[0] < a[0]);
+        let edges = [(1, 2), (2, 0), (0, 1)];
+
+        let range = |axis: usize, size: usize| {
+            let lo = window.iter().map(|v| v[axis]).min().unwrap() as f64 / SUBPIXELS;
+            let hi = window.iter().map(|v| v[axis]).max().unwrap() as f64 / SUBPIXELS;
+            pixel_range(lo, hi, size)
+        };
+        let x_range = range(0, self.width);
+        let y_range = range(1, self.height);
+        let y_range = y_range.start.max(self.rows.start)..y_range.end.min(self.rows.end);
+
+        let [v0, v1, v2] = tri.vertices;
+        let normal = (v1 - v0).cross(&(v2 - v0)).normalize();
+        let center = tri.center();
+
+        for y in y_range {
+            for x in x_range.clone() {
+                let half = SUBPIXELS as i128 / 2;
+                let p = [
+                    x as i128 * SUBPIXELS as i128 + half,
+                    y as i128 * SUBPIXELS as i128 + half,
+                ];
+
+                let mut weights = [0.; 3];
+                let mut inside = true;
+                for (k, &(i, j)) in edges.iter().enumerate() {
+                    let e = edge(window[i], window[j], p);
+                    inside &= e > 0 || (e == 0 && top_left(window[i], window[j]));
+                    weights[k] = (e as f64 / area as f64) as f32;
+                }
+                if !inside {
+                    continue;
+                }
+
+                let index = (y - self.rows.start) * self.width + x;
+                let depth: f32 = (0..3).map(|k| weights[k] * depths[k]).sum();
+                // GL_LESS, which also rejects NaN.
+                if depth.partial_cmp(&self.depth[index]) != Some(std::cmp::Ordering::Less) {
+                    continue;
+                }
+
+                // Perspective-correct interpolation.
+                let perspective: Vec<f32> =
+                    (0..3).map(|k| weights[k] / vertices[k].clip.w).collect();
+                let total: f32 = perspective.iter().sum();
+                let pos: Vector3<f32> = (0..3)
+                    .map(|k| vertices[k].pos * (perspective[k] / total))
+                    .sum();
+
+                let color = self.fragment(tri, pos, normal, center);
+
+                let dst = &mut self.pixels[index];
+                let alpha = color[3];
+                for c in 0..4 {
+                    dst[c] = color[c] * alpha + dst[c] * (1. - alpha);
+                }
+                self.depth[index] = depth;
+            }
+        }
+    }
+
+    // `fragment.glsl`.
+    fn fragment(
+        &self,
+        tri: &Triangle,
+        pos: Vector3<f32>,
+        normal: Vector3<f32>,
+        center: Vector3<f32>,
+    ) -> [f32; 4] {
+        let eye = self.uniforms.player_isometry.translation.vector;
+
+        let mut world = self.uniforms.player_world;
+        self.portal.travel(&mut world, eye, pos);
+        self.portal.travel(&mut world, pos, center);
+
+        let mut color = tri.colors.get(world as usize).copied().unwrap_or_default();
+
+        let light =
+            tri.ambient_factor + tri.diffuse_factor * normal.dot(&self.uniforms.light_dir).max(0.);
+        for c in &mut color[..3] {
+            *c *= light;
+        }
+        color
+    }
+}
+
+// The pixels whose centres might lie between `lo` and `hi`.
+fn pixel_range(lo: f64, hi: f64, size: usize) -> std::ops::Range<usize> {
+    let lo = (lo - 0.5).floor().max(0.).min(size as f64) as usize;
+    let hi = (hi + 0.5).ceil().max(0.).min(size as f64) as usize;
+    lo..hi
+}
+
+// The part of a convex polygon where `distance` is positive.
+fn clip(polygon: Vec<Vertex>, distance: impl Fn(&Vertex) -> f32) -> Vec<Vertex> {
+    let mut out = Vec::new();
+    for i in 0..polygon.len() {
+        let (a, b) = (polygon[i], polygon[(i + 1) % polygon.len()]);
+        let (da, db) = (distance(&a), distance(&b));
+        if da >= 0. {
+            out.push(a);
+        }
+        if (da >= 0.) != (db >= 0.) {
+            // Always work from the inside, so that the triangles on both sides of an edge cut it at exactly the same point.
+            let ((a, da), (b, db)) = if da >= 0. {
+                ((a, da), (b, db))
+            } else {
+                ((b, db), (a, da))
+            };
+            let s = da / (da - db);
+            out.push(Vertex {
+                clip: a.clip + (b.clip - a.clip) * s,
+                pos: a.pos + (b.pos - a.pos) * s,
+            });
+        }
+    }
+    out
+}
@@ -0,0 +1,131 @@
+//! Renders small frames of the demo scene, and checks which worlds' skies show up in them.
+
+use knotted_portal_core::knot::Knot;
+use knotted_portal_core::modeling::{self, Triangle, Uniforms};
+use knotted_portal_core::portal::Portal;
+use knotted_portal_core::Player;
+use knotted_portal_raster::{Image, Renderer};
+
+const WIDTH: usize = 96;
+const HEIGHT: usize = 72;
+
+fn render(portal: &Portal, player: &Player, balls: Vec<Triangle>) -> Image {
+    let sky = modeling::world_colors(portal.worlds());
+    let ground = vec![modeling::GROUND_COLOR; portal.worlds() as usize];
+    let renderer = Renderer::new(
+        modeling::knot(portal, &sky)
+            .chain(modeling::skybox(sky.into()))
+            .chain(modeling::ground(ground.into())),
+    );
+    renderer.render(
+        portal,
+        Uniforms {
+            light_dir: nalgebra::Vector3::new(1.0, 1.0, 1.0).normalize(),
+            player_isometry: player.isometry(),
+            player_world: player.world,
+            fov: std::f32::consts::FRAC_PI_2,
+        },
+        balls,
+        WIDTH,
+        HEIGHT,
+    )
+}
+
+// The skybox has no diffuse lighting, so its pixels are exactly the world colours.
+fn sky(portal: &Portal, world: i32) -> [u8; 4] {
+    let [r, g, b, a] = modeling::world_colors(portal.worlds())[world as usize];
+    let to_u8 = |x: f32| (x * 255.).round() as u8;
+    [to_u8(r), to_u8(g), to_u8(b), to_u8(a)]
+}
+
+fn skies_seen(portal: &Portal, image: &Image) -> Vec<i32> {
+    (0..portal.worlds())
+        .filter(|&w| {
+            (0..image.height).any(|y| (0..image.width).any(|x| image.pixel(x, y) == sky(portal, w)))
+        })
+        .collect()
+}
+
+fn sky_matches_the_players_world_in(portal: &Portal) {
+    for world in 0..portal.worlds() {
+        let player = Player {
+            world,
+            ..Player::new()
+        };
+        let image = render(portal, &player, Vec::new());
+        assert_eq!(image.pixel(0, 0), sky(portal, world), "world {}", world);
+        assert_eq!(
+            image.pixel(WIDTH - 1, 0),
+            sky(portal, world),
+            "world {}",
+            world
+        );
+    }
+}
+
+#[test]
+fn sky_matches_the_players_world() {
+    sky_matches_the_players_world_in(&Portal::trefoil());
+}
+
+// Ten worlds, more than `WORLD_COLORS` has, each with a sky of its own.
+#[test]
+fn more_than_six_worlds() {
+    let portal = Portal::dihedral(Knot::figure_eight());
+    assert_eq!(portal.worlds(), 10);
+    let skies: Vec<[u8; 4]> = (0..10).map(|w| sky(&portal, w)).collect();
+    for (w, color) in skies.iter().enumerate() {
+        assert!(
+            !skies[w + 1..].contains(color),
+            "world {} shares its sky",
+            w
+        );
+    }
+    sky_matches_the_players_world_in(&portal);
+
+    let image = render(&portal, &Player::new(), Vec::new());
+    assert!(skies_seen(&portal, &image).len() > 1);
+
+    // Facing away from the portal, at a ball in world 7, which is only seen from there.
+    let ball: Vec<Triangle> =
+        modeling::ball(nalgebra::Vector3::new(13., 0., 1.), 7, [1.; 4], 10).collect();
+    for world in 0..10 {
+        let player = Player {
+            pos: nalgebra::Vector3::new(10., 0., 1.),
+            theta: std::f32::consts::PI,
+            world,
+            ..Player::new()
+        };
+        let pixel = render(&portal, &player, ball.clone()).pixel(WIDTH / 2, HEIGHT / 2 - 1);
+        if world == 7 {
+            assert_ne!(pixel, sky(&portal, world));
+        } else {
+            assert_eq!(pixel, sky(&portal, world), "world {}", world);
+        }
+    }
+}
+
+#[test]
+fn other_worlds_show_through_the_knot() {
+    let portal = Portal::trefoil();
+    let image = render(&portal, &Player::new(), Vec::new());
+    let skies = skies_seen(&portal, &image);
+    assert!(skies.contains(&0));
+    assert!(skies.len() > 1, "only saw {:?}", skies);
+}
+
+#[test]
+fn png_round_trip() {
+    let portal = Portal::trefoil();
+    let image = render(&portal, &Player::new(), Vec::new());
+
+    let mut bytes = Vec::new();
+    image.write_png(&mut bytes).unwrap();
+
+    let decoder = png::Decoder::new(&bytes[..]);
+    let mut reader = decoder.read_info().unwrap();
+    let mut data = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut data).unwrap();
+    assert_eq!((info.width as usize, info.height as usize), (WIDTH, HEIGHT));
+    assert_eq!(&data[..info.buffer_size()], &image.to_rgba8()[..]);
+}
@@ -0,0 +1,121 @@
+use crate::State;
+use knotted_portal_core::model::{Traveller, WorldChange};
+use wasm_bindgen::prelude::*;
+
+/// A handle on the running game, for scripts on the page, such as guided tours.
+///
+/// Moving the player and adding and removing balls are recorded in replays, like the player's own input.
+#[wasm_bindgen]
+pub struct Portal {
+    state: State,
+}
+
+#[wasm_bindgen]
+impl Portal {
+    pub fn player_world(&self) -> i32 {
+        self.state.0.borrow().model.player.world
+    }
+
+    /// Move the player to (x, y, z), turned `theta` radians clockwise from facing -x, and looking `phi` radians up, in `world`.
+    pub fn set_player_pose(
+        &self,
+        x: f32,
+        y: f32,
+        z: f32,
+        theta: f32,
+        phi: f32,
+        world: i32,
+    ) -> Result<(), JsValue> {
+        self.state
+            .0
+            .borrow_mut()
+            .model
+            .set_player_pose([x, y, z], theta, phi, world)
+            .map_err(JsValue::from)
+    }
+
+    /// Add a ball that falls and bounces freely, returning its id.
+    /// `position` and `velocity` are `[x, y, z]`, and `color` is `[r, g, b]` or `[r, g, b, a]`, from 0 to 1.
+    pub fn add_ball(
+        &self,
+        position: &[f32],
+        velocity: &[f32],
+        world: i32,
+        color: &[f32],
+    ) -> Result<u32, JsValue> {
+        let color = match *color {
+            [r, g, b] => [r, g, b, 1.],
+            [r, g, b, a] => [r, g, b, a],
+            _ => return Err("a colour is [r, g, b] or [r, g, b, a]".into()),
+        };
+        self.state
+            .0
+            .borrow_mut()
+            .model
+            .add_ball(color, world, vector(position)?, vector(velocity)?)
+            .map_err(JsValue::from)
+    }
+
+    pub fn remove_ball(&self, id: u32) -> Result<(), JsValue> {
+        self.state
+            .0
+            .borrow_mut()
+            .model
+            .remove_ball(id)
+            .map_err(JsValue::from)
+    }
+
+    /// Stop the game's clock. It's still drawn, and can still be looked around.
+    pub fn pause(&self) {
+        self.state.0.borrow_mut().paused = true;
+    }
+
+    pub fn resume(&self) {
+        self.state.0.borrow_mut().paused = false;
+    }
+
+    /// Call `callback` whenever the player or a ball goes from one world to another, with an object like
+    /// `{ tick: 120, who: "ball", ball: 3, from: 0, to: 2 }`. `who` is `"player"` or `"ball"`, and `ball` is only there for balls.
+    /// Pass `null` to stop.
+    pub fn on_world_change(&self, callback: Option<js_sys::Function>) {
+        self.state.0.borrow_mut().on_world_change = callback;
+    }
+
+    /// Stop the game, unregister its event listeners and free them, and remove what it added to the page.
+    /// The canvas stays, if the page gave it to `run_in`.
+    pub fn destroy(&self) {
+        self.state.destroy();
+    }
+}
+
+impl Portal {
+    pub(crate) fn new(state: State) -> Self {
+        Self { state }
+    }
+}
+
+/// The argument for `on_world_change`'s callback.
+pub(crate) fn world_change(change: &WorldChange) -> JsValue {
+    let object = js_sys::Object::new();
+    let set = |key: &str, value: JsValue| {
+        js_sys::Reflect::set(&object, &key.into(), &value).unwrap_throw();
+    };
+    set("tick", (change.tick as f64).into());
+    match change.who {
+        Traveller::Player => set("who", "player".into()),
+        Traveller::Ball(id) => {
+            set("who", "ball".into());
+            set("ball", id.into());
+        }
+    }
+    set("from", change.from.into());
+    set("to", change.to.into());
+    object.into()
+}
+
+fn vector(xyz: &[f32]) -> Result<[f32; 3], JsValue> {
+    match *xyz {
+        [x, y, z] => Ok([x, y, z]),
+        _ => Err("a position or velocity is [x, y, z]".into()),
+    }
+}
@@ -0,0 +1,88 @@
+use crate::fps::FrameCounter;
+use knotted_portal_core::model::Model;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+/// Text over the top left of the canvas, for debugging: the frame times, where the player is, and the arcs they passed under recently.
+/// The `toggle_hud` binding, F3 by default, shows and hides it.
+pub struct Hud {
+    element: web_sys::HtmlParagraphElement,
+    visible: bool,
+}
+
+impl Hud {
+    pub fn new(document: &web_sys::Document, overlay: &web_sys::Element) -> Self {
+        let element = document
+            .create_element("p")
+            .unwrap_throw()
+            .dyn_into::<web_sys::HtmlParagraphElement>()
+            .unwrap_throw();
+        element
+            .set_attribute(
+                "style",
+                "position: absolute; top: 0; left: 0; margin: 8px; padding: 8px; display: none; \
+                 white-space: pre; font: 12px monospace; color: white; \
+                 background: rgba(0, 0, 0, 0.6); pointer-events: none;",
+            )
+            .unwrap_throw();
+        overlay.append_child(&element).unwrap_throw();
+        Self {
+            element,
+            visible: false,
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+        self.element
+            .style()
+            .set_property("display", if self.visible { "block" } else { "none" })
+            .unwrap_throw();
+    }
+
+    pub fn update(&self, model: &Model, fps: &FrameCounter) {
+        if !self.visible {
+            return;
+        }
+
+        let player = &model.player;
+        let [r, g, b, _] = model.sky()[player.world as usize];
+        let to_u8 = |x: f32| (x * 255.).round() as u8;
+
+        let mut html = format!(
+            "{}\n\
+             world {} <span style=\"display: inline-block; width: 1em; height: 1em; vertical-align: middle; background: rgb({}, {}, {});\"></span>\n\
+             position ({:.3}, {:.3}, {:.3})\n\
+             theta {:.3}, phi {:.3}\n\
+             {:?}, tick {}\n\
+             recently passed under:",
+            fps,
+            player.world,
+            to_u8(r),
+            to_u8(g),
+            to_u8(b),
+            player.pos.x,
+            player.pos.y,
+            player.pos.z,
+            player.theta,
+            player.phi,
+            player.mode,
+            model.ticks(),
+        );
+        for (tick, crossing) in model.recent_crossings() {
+            html += &format!(
+                "\n  tick {}: arc {}, {}, world {} to {}",
+                tick,
+                crossing.arc,
+                if crossing.left_to_right {
+                    "left to right"
+                } else {
+                    "right to left"
+                },
+                crossing.from,
+                crossing.to,
+            );
+        }
+        self.element.set_inner_html(&html);
+    }
+}
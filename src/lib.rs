@@ -1,140 +1,376 @@
 #![forbid(unsafe_code)]
 
+mod api;
 mod fps;
-mod modeling;
+mod hud;
+mod minimap;
 mod render;
+mod touch;
 
-/// This module is a mirror of my GLSL code. It may not be good *Rust* code, but I think having it match the GLSL is worth it.
-mod portal;
-
+use knotted_portal_core::gamepad::{self, Gamepad};
+use knotted_portal_core::model::Model;
+use knotted_portal_core::replay::{Analog, Input};
+use knotted_portal_core::settings::Settings;
+use serde::Deserialize;
 use std::cell::RefCell;
-use std::collections::HashSet;
 use std::rc::Rc;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
 
+/// Start the game on a canvas filling the page, returning a handle that scripts on the page can drive it with.
 #[wasm_bindgen]
-pub fn run() {
+pub fn run() -> api::Portal {
     std::panic::set_hook(Box::new(console_error_panic_hook::hook));
 
-    web_sys::window()
+    let document = web_sys::window().unwrap_throw().document().unwrap_throw();
+    let body = document.body().unwrap_throw();
+    body.style()
+        .set_property("background-color", "#111111")
+        .unwrap_throw();
+
+    let canvas = document
+        .create_element("canvas")
         .unwrap_throw()
-        .request_animation_frame(&State::new().0.borrow().animation_frame_closure)
+        .dyn_into::<web_sys::HtmlCanvasElement>()
+        .unwrap_throw();
+    // Less the body's margin.
+    canvas
+        .set_attribute(
+            "style",
+            "display: block; width: calc(100vw - 16px); height: calc(100vh - 16px);",
+        )
         .unwrap_throw();
+    body.append_child(&canvas).unwrap_throw();
+
+    api::Portal::new(State::new(canvas, Model::new(), Options::default(), true))
+}
+
+/// Start the game on a canvas already on the page, given either as the element or as a CSS selector.
+/// It's drawn at whatever size the page gives the canvas, and there can be several on one page.
+///
+/// `options` may be left out, or be an object with any of:
+///  * `scene`: a scene, as in `scenes/`, either as an object or as a string of JSON. The default is `scenes/trefoil.json`.
+///  * `minimap`: whether to show the minimap at the start. The default is `true`.
+#[wasm_bindgen]
+pub fn run_in(target: JsValue, options: JsValue) -> Result<api::Portal, JsValue> {
+    std::panic::set_hook(Box::new(console_error_panic_hook::hook));
+
+    let document = web_sys::window().unwrap_throw().document().unwrap_throw();
+    let element = match target.as_string() {
+        Some(selector) => document
+            .query_selector(&selector)?
+            .ok_or_else(|| format!("nothing matches {:?}", selector))?
+            .into(),
+        None => target,
+    };
+    let canvas = element
+        .dyn_into::<web_sys::HtmlCanvasElement>()
+        .map_err(|_| "the target isn't a canvas")?;
+
+    let options: Options = if options.is_undefined() || options.is_null() {
+        Options::default()
+    } else {
+        let json: String = js_sys::JSON::stringify(&options)?.into();
+        serde_json::from_str(&json).map_err(|e| format!("options: {}", e))?
+    };
+    let model = match &options.scene {
+        None => Ok(Model::new()),
+        Some(serde_json::Value::String(json)) => Model::from_json(json),
+        Some(scene) => Model::from_json(&scene.to_string()),
+    }
+    .map_err(|e| format!("scene: {}", e))?;
+
+    Ok(api::Portal::new(State::new(canvas, model, options, false)))
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Options {
+    scene: Option<serde_json::Value>,
+    minimap: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            scene: None,
+            minimap: true,
+        }
+    }
 }
 
 pub enum Msg {
     Click,
     MouseMove([i32; 2]),
+    // From the touch controls' buttons.
     KeyDown(String),
     KeyUp(String),
+    // A keydown or keyup, whole, so that `update` can tell repeats apart and keep the browser from acting on the game's keys.
+    Keyboard(web_sys::KeyboardEvent),
+    // The canvas lost the keyboard, so the keys held won't be let go of as far as it knows.
+    Blur,
+    // The canvas's new size, in CSS pixels.
+    Resize([f64; 2]),
+    // The GPU was reset, or the browser reclaimed the context, and everything on the GPU is gone until it's restored.
+    ContextLost,
+    ContextRestored,
+    Touch(touch::Phase, Vec<(i32, [f32; 2])>),
 }
 
 #[derive(Clone)]
-struct State(Rc<RefCell<Model>>);
+struct State(Rc<RefCell<App>>);
+
+struct App {
+    // Each closure holds on to the `State`, so they have to be dropped, in `destroy`, for the app to be freed.
+    animation_frame_closure: Option<Closure<dyn FnMut(f64)>>,
+    animation_frame: Option<i32>,
+    listeners: Vec<Listener>,
+    resize_observer: Option<web_sys::ResizeObserver>,
+    resize_closure: Option<Closure<dyn FnMut(js_sys::Array)>>,
 
-struct Model {
-    animation_frame_closure: js_sys::Function,
-    keys: HashSet<String>,
     fps: Option<fps::FrameCounter>,
-    renderer: render::Renderer,
+    // `None` while the WebGL context is lost, and after `destroy`.
+    renderer: Option<render::Renderer>,
 
     window: web_sys::Window,
     document: web_sys::Document,
     canvas: web_sys::HtmlCanvasElement,
+    // Whether `run` made the canvas, rather than the page, so `destroy` should remove it.
+    owns_canvas: bool,
+    // Over the canvas, holding the HUD, the minimap and the touch controls.
+    overlay: web_sys::HtmlElement,
+    // Says why there's nothing to see, if the renderer couldn't start.
+    error: web_sys::HtmlElement,
+
+    model: Model,
+    gamepad: Gamepad,
+    touch: touch::TouchControls,
+    // The latest analog input from each device. See `Analog::combine`.
+    gamepad_analog: Analog,
+    touch_analog: Analog,
+    hud: hud::Hud,
+    minimap: minimap::Minimap,
+
+    // Set from the page's scripts. See `api.rs`.
+    paused: bool,
+    on_world_change: Option<js_sys::Function>,
+}
 
-    player: Player,
-    balls: Vec<Ball>,
+#[derive(Clone, Copy)]
+enum Device {
+    Gamepad,
+    Touch,
+}
+
+struct Listener {
+    target: web_sys::EventTarget,
+    event: &'static str,
+    closure: Closure<dyn FnMut(web_sys::Event)>,
 }
 
 impl State {
-    fn new() -> Self {
-        let out = Self(Rc::new(RefCell::new(Model::new())));
+    fn new(
+        canvas: web_sys::HtmlCanvasElement,
+        model: Model,
+        options: Options,
+        owns_canvas: bool,
+    ) -> Self {
+        let out = Self(Rc::new(RefCell::new(App::new(
+            canvas,
+            model,
+            options,
+            owns_canvas,
+        ))));
 
         {
-            let model: &mut Model = &mut out.0.borrow_mut();
+            let app: &mut App = &mut out.0.borrow_mut();
+            let mut listeners = Vec::new();
+            let canvas = &app.canvas;
 
-            out.event_listener(&model.canvas, "mousedown", move |_| Msg::Click);
-            out.event_listener(&model.canvas, "mousemove", |evt| {
+            listeners.push(out.event_listener(canvas, "mousedown", move |_| Msg::Click));
+            listeners.push(out.event_listener(canvas, "mousemove", |evt| {
                 let evt = evt.dyn_into::<web_sys::MouseEvent>().unwrap_throw();
                 Msg::MouseMove([evt.movement_x(), evt.movement_y()])
-            });
-            out.event_listener(&model.document, "keydown", |evt| {
-                let evt = evt.dyn_into::<web_sys::KeyboardEvent>().unwrap_throw();
-                Msg::KeyDown(evt.key())
-            });
-            out.event_listener(&model.document, "keyup", |evt| {
-                let evt = evt.dyn_into::<web_sys::KeyboardEvent>().unwrap_throw();
-                Msg::KeyUp(evt.key())
-            });
+            }));
+            // Only while the canvas has the focus, so that several on one page don't all move at once.
+            for &event in &["keydown", "keyup"] {
+                listeners.push(out.event_listener(canvas, event, |evt| {
+                    Msg::Keyboard(evt.dyn_into::<web_sys::KeyboardEvent>().unwrap_throw())
+                }));
+            }
+            listeners.push(out.event_listener(canvas, "blur", |_| Msg::Blur));
+            listeners.push(out.event_listener(canvas, "webglcontextlost", |evt| {
+                // Otherwise, the context is never restored.
+                evt.prevent_default();
+                Msg::ContextLost
+            }));
+            listeners
+                .push(out.event_listener(canvas, "webglcontextrestored", |_| Msg::ContextRestored));
+
+            listeners.push(out.event_listener(canvas, "touchstart", |evt| {
+                Msg::Touch(touch::Phase::Start, touch::fingers(evt))
+            }));
+            listeners.push(out.event_listener(canvas, "touchmove", |evt| {
+                Msg::Touch(touch::Phase::Move, touch::fingers(evt))
+            }));
+            for &event in &["touchend", "touchcancel"] {
+                listeners.push(out.event_listener(canvas, event, |evt| {
+                    Msg::Touch(touch::Phase::End, touch::fingers(evt))
+                }));
+            }
+            for (button, key) in &[(&app.touch.up, "TouchUp"), (&app.touch.down, "TouchDown")] {
+                let key = key.to_string();
+                let down = key.clone();
+                listeners.push(out.event_listener(button, "touchstart", move |evt| {
+                    evt.prevent_default();
+                    Msg::KeyDown(down.clone())
+                }));
+                for &event in &["touchend", "touchcancel"] {
+                    let up = key.clone();
+                    listeners.push(out.event_listener(button, event, move |evt| {
+                        evt.prevent_default();
+                        Msg::KeyUp(up.clone())
+                    }));
+                }
+            }
+            app.listeners = listeners;
+
+            let state = out.clone();
+            let closure: Closure<dyn FnMut(js_sys::Array)> =
+                Closure::wrap(Box::new(move |entries: js_sys::Array| {
+                    if let Some(entry) = entries.iter().last() {
+                        let rect = entry
+                            .unchecked_into::<web_sys::ResizeObserverEntry>()
+                            .content_rect();
+                        state.update(Msg::Resize([rect.width(), rect.height()]));
+                    }
+                }));
+            let observer =
+                web_sys::ResizeObserver::new(closure.as_ref().unchecked_ref()).unwrap_throw();
+            observer.observe(&app.canvas);
+            app.resize_observer = Some(observer);
+            app.resize_closure = Some(closure);
 
             let state = out.clone();
-            let closure: Closure<dyn FnMut(f64)> = Closure::wrap(Box::new(move |timestamp| {
+            app.animation_frame_closure = Some(Closure::wrap(Box::new(move |timestamp| {
                 state.frame(timestamp);
-            }));
-            model.animation_frame_closure =
-                closure.as_ref().unchecked_ref::<js_sys::Function>().clone();
-            closure.forget();
+            })));
+            app.request_animation_frame();
         }
 
         out
     }
 
     fn update(&self, msg: Msg) {
-        let model: &mut Model = &mut self.0.borrow_mut();
+        let app: &mut App = &mut self.0.borrow_mut();
 
         match msg {
             Msg::Click => {
-                if model.document.pointer_lock_element().is_none() {
-                    model.canvas.request_pointer_lock();
+                app.canvas.focus().unwrap_throw();
+                if !app.pointer_locked() {
+                    app.canvas.request_pointer_lock();
                 }
             }
-            Msg::KeyDown(k) => {
-                model.keys.insert(k.to_lowercase());
-            }
-            Msg::KeyUp(k) => {
-                model.keys.remove(&k.to_lowercase());
+            Msg::KeyDown(k) => app.key_down(&k),
+            Msg::KeyUp(k) => app.key_up(&k),
+            Msg::Keyboard(evt) => {
+                let key = evt.code();
+                // So that the arrows and space don't scroll the page, F3 doesn't search it, and so on.
+                if app.model.settings.bindings.contains(&key) {
+                    evt.prevent_default();
+                }
+                if evt.type_() == "keyup" {
+                    app.key_up(&key);
+                } else if !evt.repeat() {
+                    // A held key repeats, but it's down already, and the HUD, the minimap and saving should only toggle once.
+                    app.key_down(&key);
+                }
             }
+            // While a replay plays, the player's own input is ignored, as it has the replay's.
+            Msg::Blur | Msg::MouseMove(_) if app.model.replaying() => {}
+            Msg::Blur => app.model.release_keys(),
             Msg::MouseMove([x, y]) => {
-                if model.document.pointer_lock_element().is_some() {
-                    model.player.theta += x as f32 * 3e-3;
-                    model.player.phi -= y as f32 * 3e-3;
-
-                    model.player.phi = model
-                        .player
-                        .phi
-                        .max(-std::f32::consts::FRAC_PI_2 + 0.001)
-                        .min(std::f32::consts::FRAC_PI_2 - 0.001);
+                if app.pointer_locked() {
+                    app.model.look([x as f32, y as f32]);
+                }
+            }
+            Msg::Resize([width, height]) => {
+                // One pixel of the drawing for each of the screen's, so it's sharp on high-density screens.
+                let ratio = app.window.device_pixel_ratio();
+                app.canvas.set_width((width * ratio).round() as u32);
+                app.canvas.set_height((height * ratio).round() as u32);
+            }
+            Msg::ContextLost => {
+                web_sys::console::warn_1(&"WebGL context lost".into());
+                app.renderer = None;
+            }
+            Msg::ContextRestored => app.start_renderer(),
+            Msg::Touch(phase, fingers) => {
+                let canvas = app.canvas.get_bounding_client_rect();
+                // The controls still follow the fingers during a replay.
+                for input in app.touch.touch(phase, fingers, &canvas) {
+                    let input = app.combine_analog(input, Device::Touch);
+                    if !app.model.replaying() {
+                        app.model.input(input);
+                    }
                 }
             }
         }
     }
 
     fn frame(&self, timestamp: f64) {
-        let model: &mut Model = &mut self.0.borrow_mut();
+        let (changes, callback) = {
+            let app: &mut App = &mut self.0.borrow_mut();
+
+            app.place_overlay();
+            // The first frame only starts the clock.
+            if let Some(dt) = app.fps.as_mut().map(|fps| fps.frame(timestamp)) {
+                if !app.paused {
+                    // Only the canvas with the focus takes the gamepad's input.
+                    let state = if app.document.active_element().as_ref() == Some(&**app.canvas) {
+                        gamepad_state(&app.window)
+                    } else {
+                        gamepad::State::default()
+                    };
+                    for input in app.gamepad.poll(&state, &app.model.settings) {
+                        let input = app.combine_analog(input, Device::Gamepad);
+                        let action = matches!(&input, Input::KeyDown(key) if app.action(key));
+                        if !action && !app.model.replaying() {
+                            app.model.input(input);
+                        }
+                    }
+                    app.model.update(dt as f32);
+                }
+                if let Some(fps) = &app.fps {
+                    app.hud.update(&app.model, fps);
+                }
+                app.minimap.update(&app.model);
+                app.view();
+            } else {
+                app.fps = Some(<fps::FrameCounter>::new(timestamp));
+            }
 
-        if let Some(fps) = &mut model.fps {
-            let dt = fps.frame(timestamp);
+            app.request_animation_frame();
 
-            model.move_player(dt as f32);
-            model.move_balls(dt as f32);
-            model.view();
-        } else {
-            model.fps = Some(<fps::FrameCounter>::new(timestamp));
-        }
+            (app.model.take_world_changes(), app.on_world_change.clone())
+        };
 
-        model
-            .window
-            .request_animation_frame(&model.animation_frame_closure)
-            .unwrap_throw();
+        // After letting go of the app, since the callback may well use the `Portal` handle.
+        if let Some(callback) = callback {
+            for change in &changes {
+                if let Err(e) = callback.call1(&JsValue::NULL, &api::world_change(change)) {
+                    web_sys::console::error_1(&e);
+                }
+            }
+        }
     }
 
     fn event_listener(
         &self,
         target: &web_sys::EventTarget,
-        event: &str,
+        event: &'static str,
         msg: impl Fn(web_sys::Event) -> Msg + 'static,
-    ) {
+    ) -> Listener {
         let state = self.clone();
         let closure: Closure<dyn FnMut(web_sys::Event)> = Closure::wrap(Box::new(move |evt| {
             state.update(msg(evt));
@@ -142,180 +378,334 @@ impl State {
         target
             .add_event_listener_with_callback(event, closure.as_ref().unchecked_ref())
             .unwrap_throw();
-        closure.forget();
+        Listener {
+            target: target.clone(),
+            event,
+            closure,
+        }
+    }
+
+    /// Stop the game, and remove everything it added to the page.
+    fn destroy(&self) {
+        let app: &mut App = &mut self.0.borrow_mut();
+
+        if let Some(id) = app.animation_frame.take() {
+            app.window.cancel_animation_frame(id).unwrap_throw();
+        }
+        app.animation_frame_closure = None;
+        for listener in app.listeners.drain(..) {
+            listener
+                .target
+                .remove_event_listener_with_callback(
+                    listener.event,
+                    listener.closure.as_ref().unchecked_ref(),
+                )
+                .unwrap_throw();
+        }
+        if let Some(observer) = app.resize_observer.take() {
+            observer.disconnect();
+        }
+        app.resize_closure = None;
+        if app.pointer_locked() {
+            app.document.exit_pointer_lock();
+        }
+
+        app.renderer = None;
+        app.overlay.remove();
+        if app.owns_canvas {
+            // Free the context now, rather than whenever the canvas is collected, as browsers only allow a few at once.
+            if let Ok(Some(gl)) = app.canvas.get_context("webgl2") {
+                if let Ok(Some(extension)) = gl
+                    .unchecked_into::<web_sys::WebGl2RenderingContext>()
+                    .get_extension("WEBGL_lose_context")
+                {
+                    extension
+                        .unchecked_into::<web_sys::WebglLoseContext>()
+                        .lose_context();
+                }
+            }
+            app.canvas.remove();
+        }
     }
 }
 
-impl Model {
-    fn new() -> Self {
+impl App {
+    fn new(
+        canvas: web_sys::HtmlCanvasElement,
+        mut model: Model,
+        options: Options,
+        owns_canvas: bool,
+    ) -> Self {
         let window = web_sys::window().unwrap_throw();
         let document = window.document().unwrap_throw();
-        let body = document.body().unwrap_throw();
 
-        body.style()
-            .set_property("background-color", "#111111")
+        // So that it can take the keyboard's focus, which it takes straight away, so the keys work without a click first.
+        if !canvas.has_attribute("tabindex") {
+            canvas.set_tab_index(0);
+        }
+        canvas.focus().unwrap_throw();
+
+        let overlay = document
+            .create_element("div")
+            .unwrap_throw()
+            .dyn_into::<web_sys::HtmlElement>()
+            .unwrap_throw();
+        overlay
+            .set_attribute(
+                "style",
+                "position: fixed; overflow: hidden; pointer-events: none;",
+            )
+            .unwrap_throw();
+        document
+            .body()
+            .unwrap_throw()
+            .append_child(&overlay)
             .unwrap_throw();
 
-        let canvas = document
-            .create_element("canvas")
+        let error = document
+            .create_element("pre")
             .unwrap_throw()
-            .dyn_into::<web_sys::HtmlCanvasElement>()
+            .dyn_into::<web_sys::HtmlElement>()
             .unwrap_throw();
-        canvas.set_attribute("width", "800px").unwrap_throw();
-        canvas.set_attribute("height", "800px").unwrap_throw();
-        canvas
-            .style()
-            .set_property("grid-area", "canvas")
+        error
+            .set_attribute(
+                "style",
+                "position: absolute; top: 0; left: 0; right: 0; margin: 8px; padding: 8px; display: none; \
+                 white-space: pre-wrap; font: 12px monospace; color: #ff8080; \
+                 background: rgba(0, 0, 0, 0.8); pointer-events: auto; user-select: text;",
+            )
             .unwrap_throw();
-        body.append_child(&canvas).unwrap_throw();
-
-        let static_geometry = modeling::trefoil()
-            .chain(modeling::skybox())
-            .chain(modeling::ground());
-
-        let balls = vec![
-            Ball::new([0.6, 0.6, 0.8, 1.0], 0, |t| {
-                let (s, c) = t.sin_cos();
-                nalgebra::Vector3::new(2. * s, -2. * c, 0.)
-            }),
-            Ball::new([0.8, 0.6, 0.2, 1.0], 3, |t| {
-                let (s, c) = t.sin_cos();
-                nalgebra::Vector3::new(0.1, -3. + c, s)
-            }),
-            Ball::new([0.2, 0.3, 0.9, 1.0], 3, |t| {
-                let (s, c) = t.sin_cos();
-                let (s2, c2) = (2. * t).sin_cos();
-                nalgebra::Vector3::new(s + 2. * s2, c - 2. * c2 + 0.1, (3. * t).sin() + 0.5)
-            }),
-        ];
+        overlay.append_child(&error).unwrap_throw();
+
+        model.settings = load_settings(&window);
+        let touch = touch::TouchControls::new(&document, &overlay);
+        let hud = hud::Hud::new(&document, &overlay);
+        let mut minimap = minimap::Minimap::new(&document, &overlay, &model.portal);
+        if !options.minimap {
+            minimap.toggle();
+        }
 
-        Self {
-            animation_frame_closure: JsValue::undefined().into(),
-            fps: None,
-            keys: HashSet::new(),
-            renderer: render::Renderer::new(&canvas, static_geometry),
+        let mut out = Self {
+            animation_frame_closure: None,
+            animation_frame: None,
+            listeners: Vec::new(),
+            resize_observer: None,
+            resize_closure: None,
 
-            player: Player::new(),
+            fps: None,
+            renderer: None,
 
             window,
             document,
             canvas,
+            owns_canvas,
+            overlay,
+            error,
+
+            model,
+            gamepad: Gamepad::new(),
+            touch,
+            gamepad_analog: Analog::default(),
+            touch_analog: Analog::default(),
+            hud,
+            minimap,
+
+            paused: false,
+            on_world_change: None,
+        };
+        out.place_overlay();
+        out.start_renderer();
+        out
+    }
 
-            balls,
-        }
+    // Make a new renderer, or show why it couldn't be made.
+    fn start_renderer(&mut self) {
+        self.renderer = match render::Renderer::new(
+            &self.canvas,
+            self.model.static_geometry(),
+            &self.model.portal,
+        ) {
+            Ok(renderer) => {
+                self.error
+                    .style()
+                    .set_property("display", "none")
+                    .unwrap_throw();
+                Some(renderer)
+            }
+            Err(e) => {
+                self.show_error(&e);
+                None
+            }
+        };
     }
 
-    fn move_player(&mut self, dt: f32) {
-        let speed = 0.5;
+    fn show_error(&self, e: &render::Error) {
+        let message = e.to_string();
+        web_sys::console::error_1(&message.as_str().into());
+        self.error.set_inner_text(&message);
+        self.error
+            .style()
+            .set_property("display", "block")
+            .unwrap_throw();
+    }
 
-        let mut v = nalgebra::Vector3::zeros();
-        if self.keys.contains(" ") {
-            v += nalgebra::Vector3::z() * dt * speed;
-        }
-        if self.keys.contains("shift") {
-            v -= nalgebra::Vector3::z() * dt * speed;
+    fn request_animation_frame(&mut self) {
+        if let Some(closure) = &self.animation_frame_closure {
+            self.animation_frame = Some(
+                self.window
+                    .request_animation_frame(closure.as_ref().unchecked_ref())
+                    .unwrap_throw(),
+            );
         }
-        if self.keys.contains("w") {
-            v -= nalgebra::Vector3::x() * dt * speed;
-        }
-        if self.keys.contains("s") {
-            v += nalgebra::Vector3::x() * dt * speed;
-        }
-        if self.keys.contains("a") {
-            v -= nalgebra::Vector3::y() * dt * speed;
+    }
+
+    // Analog input from either device stands for both of them together, so that one doesn't zero the other.
+    fn combine_analog(&mut self, input: Input, device: Device) -> Input {
+        match input {
+            Input::Analog(analog) => {
+                match device {
+                    Device::Gamepad => self.gamepad_analog = analog,
+                    Device::Touch => self.touch_analog = analog,
+                }
+                Input::Analog(self.gamepad_analog.combine(self.touch_analog))
+            }
+            input => input,
         }
-        if self.keys.contains("d") {
-            v += nalgebra::Vector3::y() * dt * speed;
+    }
+
+    fn key_down(&mut self, key: &str) {
+        if !self.action(key) && !self.model.replaying() {
+            self.model.key_down(key);
         }
+    }
 
-        v = nalgebra::UnitQuaternion::new(-self.player.theta * nalgebra::Vector3::z()) * v;
-        self.player.travel(v);
+    fn key_up(&mut self, key: &str) {
+        if !self.model.replaying() {
+            self.model.key_up(key);
+        }
     }
 
-    fn move_balls(&mut self, dt: f32) {
-        for ball in self.balls.iter_mut() {
-            ball.travel(dt);
+    // Do what `key` is bound to in the front-end itself, if anything, returning whether it was.
+    fn action(&mut self, key: &str) -> bool {
+        let bindings = &self.model.settings.bindings;
+        let bound = |keys: &[String]| keys.iter().any(|k| k == key);
+        if bound(&bindings.save_replay) {
+            self.save_replay();
+        } else if bound(&bindings.toggle_hud) {
+            self.hud.toggle();
+        } else if bound(&bindings.toggle_minimap) {
+            self.minimap.toggle();
+        } else {
+            return false;
         }
+        true
     }
 
-    fn view(&self) {
-        self.renderer.render(
-            render::Uniforms {
-                light_dir: nalgebra::Vector3::new(1.0, 1.0, 1.0).normalize(),
-                player_isometry: self.player.isometry(),
-                player_world: self.player.world,
-            },
-            self.balls.iter().flat_map(Ball::geometry).collect(),
-        )
+    fn pointer_locked(&self) -> bool {
+        self.document.pointer_lock_element().as_ref() == Some(&**self.canvas)
     }
-}
 
-struct Player {
-    pos: nalgebra::Vector3<f32>,
-    theta: f32,
-    phi: f32,
-    world: i32,
-}
+    // Keep the overlay over the canvas, wherever the page has moved it.
+    fn place_overlay(&self) {
+        let rect = self.canvas.get_bounding_client_rect();
+        let style = self.overlay.style();
+        for &(property, value) in &[
+            ("left", rect.left()),
+            ("top", rect.top()),
+            ("width", rect.width()),
+            ("height", rect.height()),
+        ] {
+            style
+                .set_property(property, &format!("{}px", value))
+                .unwrap_throw();
+        }
+    }
 
-impl Player {
-    fn new() -> Self {
-        Self {
-            pos: nalgebra::Vector3::new(5.0, 0.0, 0.0),
-            theta: 0.,
-            phi: 0.,
-            world: 0,
+    fn view(&mut self) {
+        let result = match &self.renderer {
+            Some(renderer) => renderer.render(self.model.uniforms(), self.model.dynamic_geometry()),
+            None => return,
+        };
+        if let Err(e) = result {
+            self.renderer = None;
+            self.show_error(&e);
         }
     }
 
-    // Player space -> World space
-    fn isometry(&self) -> nalgebra::Isometry3<f32> {
-        let (st, ct) = self.theta.sin_cos();
-        let (sp, cp) = self.phi.sin_cos();
-
-        nalgebra::Isometry3::face_towards(
-            &nalgebra::Point3 { coords: self.pos },
-            &nalgebra::Point3 {
-                coords: self.pos + nalgebra::Vector3::new(ct * cp, -st * cp, -sp),
-            },
-            &nalgebra::Vector3::z(),
+    // Download a replay of the game so far.
+    fn save_replay(&self) {
+        let json = self.model.replay().to_json();
+        let options = web_sys::BlobPropertyBag::new();
+        options.set_type("application/json");
+        let blob = web_sys::Blob::new_with_str_sequence_and_options(
+            &js_sys::Array::of1(&JsValue::from_str(&json)),
+            &options,
         )
-    }
+        .unwrap_throw();
+        let url = web_sys::Url::create_object_url_with_blob(&blob).unwrap_throw();
 
-    fn travel(&mut self, v: nalgebra::Vector3<f32>) {
-        let newpos = self.pos + v;
-        portal::travel(&mut self.world, self.pos, newpos);
-        self.pos = newpos;
+        let link = self
+            .document
+            .create_element("a")
+            .unwrap_throw()
+            .dyn_into::<web_sys::HtmlAnchorElement>()
+            .unwrap_throw();
+        link.set_href(&url);
+        link.set_download("replay.json");
+        link.click();
+        web_sys::Url::revoke_object_url(&url).unwrap_throw();
     }
 }
 
-struct Ball {
-    color: [f32; 4],
-    path: fn(f32) -> nalgebra::Vector3<f32>,
-    pos: nalgebra::Vector3<f32>,
-    t: f32,
-    world: i32,
+// The settings are kept in `localStorage`, where they can be edited from the browser's console.
+const SETTINGS_KEY: &str = "knotted-portal-settings";
+
+fn load_settings(window: &web_sys::Window) -> Settings {
+    let storage = match window.local_storage() {
+        Ok(Some(storage)) => storage,
+        // Storage is disabled.
+        _ => return Settings::default(),
+    };
+    let settings = match storage.get_item(SETTINGS_KEY).unwrap_throw() {
+        None => Settings::default(),
+        Some(json) => match Settings::from_json(&json) {
+            Ok(settings) => settings,
+            Err(e) => {
+                // Leave them for the player to fix.
+                web_sys::console::warn_1(&format!("{}: {}", SETTINGS_KEY, e).into());
+                return Settings::default();
+            }
+        },
+    };
+    // Write them back, so that the fields left out show up with their defaults.
+    let _ = storage.set_item(SETTINGS_KEY, &settings.to_json());
+    settings
 }
 
-impl Ball {
-    fn new(color: [f32; 4], world: i32, path: fn(f32) -> nalgebra::Vector3<f32>) -> Self {
-        Self {
-            color,
-            path,
-            t: 0.,
-            pos: path(0.),
-            world,
-        }
-    }
-
-    fn travel(&mut self, dt: f32) {
-        let t = self.t + dt;
-        let pos = (self.path)(t);
-        portal::travel(&mut self.world, self.pos, pos);
-        self.t = t;
-        self.pos = pos;
-    }
-
-    fn geometry(&self) -> impl IntoIterator<Item = modeling::Triangle> {
-        modeling::ball((self.path)(self.t), self.world, self.color)
+// The first connected gamepad, or no input if there isn't one.
+fn gamepad_state(window: &web_sys::Window) -> gamepad::State {
+    let gamepads = match window.navigator().get_gamepads() {
+        Ok(gamepads) => gamepads,
+        // Not allowed here, such as in an insecure context.
+        Err(_) => return gamepad::State::default(),
+    };
+    let gamepad = gamepads
+        .iter()
+        .filter_map(|g| g.dyn_into::<web_sys::Gamepad>().ok())
+        .find(|g| g.connected());
+    match gamepad {
+        None => gamepad::State::default(),
+        Some(gamepad) => gamepad::State {
+            axes: gamepad
+                .axes()
+                .iter()
+                .map(|x| x.as_f64().unwrap_or(0.) as f32)
+                .collect(),
+            buttons: gamepad
+                .buttons()
+                .iter()
+                .map(|b| b.unchecked_into::<web_sys::GamepadButton>().value() as f32)
+                .collect(),
+        },
     }
 }
@@ -0,0 +1,181 @@
+use knotted_portal_core::minimap;
+use knotted_portal_core::model::Model;
+use knotted_portal_core::portal::Portal;
+use std::f64::consts::TAU;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+// The size of each panel, in CSS pixels.
+const PANEL: f64 = 160.;
+
+// The colour of each arc, in the knot diagram and on the edges of the graph.
+const ARC_COLORS: [&str; 6] = [
+    "#ff7070", "#70c0ff", "#80e070", "#ffd050", "#d080ff", "#60e0d0",
+];
+
+/// The knot diagram with the player and the balls on it, and the graph of the worlds,
+/// drawn over the bottom left of the canvas. The `toggle_minimap` binding, M by default, shows and hides it.
+pub struct Minimap {
+    layout: minimap::Minimap,
+    canvas: web_sys::HtmlCanvasElement,
+    context: web_sys::CanvasRenderingContext2d,
+    visible: bool,
+}
+
+impl Minimap {
+    pub fn new(document: &web_sys::Document, overlay: &web_sys::Element, portal: &Portal) -> Self {
+        let canvas = document
+            .create_element("canvas")
+            .unwrap_throw()
+            .dyn_into::<web_sys::HtmlCanvasElement>()
+            .unwrap_throw();
+        let ratio = web_sys::window().unwrap_throw().device_pixel_ratio();
+        canvas.set_width((2. * PANEL * ratio) as u32);
+        canvas.set_height((PANEL * ratio) as u32);
+        canvas
+            .set_attribute(
+                "style",
+                &format!(
+                    "position: absolute; bottom: 0; left: 0; margin: 8px; width: {}px; height: {}px; \
+                     background: rgba(0, 0, 0, 0.6); pointer-events: none;",
+                    2. * PANEL,
+                    PANEL,
+                ),
+            )
+            .unwrap_throw();
+        overlay.append_child(&canvas).unwrap_throw();
+
+        let context = canvas
+            .get_context("2d")
+            .unwrap_throw()
+            .unwrap_throw()
+            .dyn_into::<web_sys::CanvasRenderingContext2d>()
+            .unwrap_throw();
+        context.scale(ratio, ratio).unwrap_throw();
+
+        Self {
+            layout: minimap::Minimap::new(portal),
+            canvas,
+            context,
+            visible: true,
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+        self.canvas
+            .style()
+            .set_property("display", if self.visible { "block" } else { "none" })
+            .unwrap_throw();
+    }
+
+    pub fn update(&self, model: &Model) {
+        if !self.visible {
+            return;
+        }
+        let cx = &self.context;
+        cx.clear_rect(0., 0., 2. * PANEL, PANEL);
+        cx.set_line_cap("round");
+        cx.set_font("11px monospace");
+        cx.set_text_align("center");
+        cx.set_text_baseline("middle");
+
+        let at =
+            |[x, y]: [f32; 2], panel: f64| (panel * PANEL + x as f64 * PANEL, y as f64 * PANEL);
+
+        // The knot diagram.
+        cx.set_line_width(3.);
+        for (k, arc) in self.layout.arcs.iter().enumerate() {
+            cx.set_stroke_style_str(arc_color(k));
+            cx.begin_path();
+            for &p in arc {
+                let (x, y) = at(p, 0.);
+                cx.line_to(x, y);
+            }
+            cx.stroke();
+        }
+
+        for ball in &model.balls {
+            let (x, y) = at(self.layout.place(ball.pos), 0.);
+            let [r, g, b, _] = ball.color;
+            cx.set_fill_style_str(&css_color([r, g, b]));
+            // Balls in other worlds are faint.
+            cx.set_global_alpha(if ball.world == model.player.world {
+                1.
+            } else {
+                0.3
+            });
+            dot(cx, x, y, 3.);
+        }
+        cx.set_global_alpha(1.);
+
+        let player = &model.player;
+        let (x, y) = at(self.layout.place(player.pos), 0.);
+        let [dx, dy] = minimap::Minimap::heading(player.theta);
+        cx.set_stroke_style_str("white");
+        cx.set_line_width(2.);
+        cx.begin_path();
+        cx.move_to(x, y);
+        cx.line_to(x + 10. * dx as f64, y + 10. * dy as f64);
+        cx.stroke();
+        cx.set_fill_style_str("white");
+        dot(cx, x, y, 4.);
+
+        // The graph of the worlds, with the arcs passed under recently drawn thicker, the most recent the brightest.
+        let recent: Vec<_> = model.recent_crossings().collect();
+        for edge in &self.layout.edges {
+            let (x0, y0) = at(self.layout.worlds[edge.from as usize], 1.);
+            let (x1, y1) = at(self.layout.worlds[edge.to as usize], 1.);
+            let age = recent.iter().rev().position(|(_, c)| {
+                c.arc == edge.arc && c.from.min(c.to) == edge.from && c.from.max(c.to) == edge.to
+            });
+            let (width, alpha) = match age {
+                Some(age) => (4., 1. - 0.1 * age as f64),
+                None => (1., 0.5),
+            };
+            cx.set_stroke_style_str(arc_color(edge.arc));
+            cx.set_line_width(width);
+            cx.set_global_alpha(alpha);
+            cx.begin_path();
+            cx.move_to(x0, y0);
+            cx.line_to(x1, y1);
+            cx.stroke();
+        }
+        cx.set_global_alpha(1.);
+
+        let sky = model.sky();
+        let radius = (0.3 * PANEL / self.layout.worlds.len() as f64).clamp(3., 10.);
+        for (w, &p) in self.layout.worlds.iter().enumerate() {
+            let (x, y) = at(p, 1.);
+            let [r, g, b, _] = sky[w];
+            cx.set_fill_style_str(&css_color([r, g, b]));
+            dot(cx, x, y, radius);
+            if w as i32 == player.world {
+                cx.set_stroke_style_str("white");
+                cx.set_line_width(2.);
+                cx.begin_path();
+                cx.arc(x, y, radius + 3., 0., TAU).unwrap_throw();
+                cx.stroke();
+            }
+            if radius >= 8. {
+                cx.set_fill_style_str("black");
+                cx.fill_text(&w.to_string(), x, y).unwrap_throw();
+            }
+        }
+    }
+}
+
+fn arc_color(arc: usize) -> &'static str {
+    ARC_COLORS[arc % ARC_COLORS.len()]
+}
+
+fn css_color(rgb: [f32; 3]) -> String {
+    let [r, g, b] = rgb.map(|x| (x * 255.).round() as u8);
+    format!("rgb({}, {}, {})", r, g, b)
+}
+
+fn dot(cx: &web_sys::CanvasRenderingContext2d, x: f64, y: f64, radius: f64) {
+    cx.begin_path();
+    cx.arc(x, y, radius, 0., TAU).unwrap_throw();
+    cx.fill();
+}
@@ -7,6 +7,7 @@ pub struct Renderer {
     canvas: web_sys::HtmlCanvasElement,
     gl: GL,
     program: web_sys::WebGlProgram,
+    uniforms: Locations,
 
     vao_static: web_sys::WebGlVertexArrayObject,
     vertex_buffer_static: web_sys::WebGlBuffer,
@@ -14,50 +15,64 @@ pub struct Renderer {
     vao_dynamic: web_sys::WebGlVertexArrayObject,
     vertex_buffer_dynamic: web_sys::WebGlBuffer,
 
+    transition_table: web_sys::WebGlTexture,
+    palette_texture: web_sys::WebGlTexture,
+    // The static geometry's palettes.
+    palettes: Palettes,
+
     num_verts_static: usize,
 }
 
+// Everything the renderer made on the GPU. The shaders are deleted as soon as the program is linked.
+// After the context is lost, these do nothing, and a new `Renderer` makes everything again from the model's geometry.
 impl Drop for Renderer {
     fn drop(&mut self) {
         self.gl.delete_program(Some(&self.program));
         self.gl.delete_vertex_array(Some(&self.vao_static));
         self.gl.delete_buffer(Some(&self.vertex_buffer_static));
+        self.gl.delete_vertex_array(Some(&self.vao_dynamic));
+        self.gl.delete_buffer(Some(&self.vertex_buffer_dynamic));
+        self.gl.delete_texture(Some(&self.transition_table));
+        self.gl.delete_texture(Some(&self.palette_texture));
     }
 }
 
 impl Renderer {
     pub fn new(
         canvas: &web_sys::HtmlCanvasElement,
-        static_geometry: impl IntoIterator<Item = crate::modeling::Triangle>,
-    ) -> Self {
+        static_geometry: impl IntoIterator<Item = knotted_portal_core::modeling::Triangle>,
+        portal: &knotted_portal_core::portal::Portal,
+    ) -> Result<Self, Error> {
+        // The transition table holds worlds in bytes.
+        if portal.worlds() > 256 {
+            return Err(Error::TooManyWorlds(portal.worlds()));
+        }
+        let polyline = portal
+            .knot
+            .polyline(knotted_portal_core::knot::SHADER_VERTICES)
+            .ok_or_else(|| Error::TooManyCrossings(portal.knot.arcs()))?;
+
         let gl = canvas
             .get_context("webgl2")
-            .unwrap_throw()
-            .unwrap_throw()
-            .dyn_into::<GL>()
-            .unwrap_throw();
+            .ok()
+            .flatten()
+            .and_then(|context| context.dyn_into::<GL>().ok())
+            .ok_or(Error::NoWebGl2)?;
 
         gl.enable(GL::DEPTH_TEST);
         gl.enable(GL::CULL_FACE);
         gl.enable(GL::BLEND);
         gl.blend_func(GL::SRC_ALPHA, GL::ONE_MINUS_SRC_ALPHA);
 
-        let vertex_shader = gl.create_shader(GL::VERTEX_SHADER).unwrap_throw();
-        gl.shader_source(&vertex_shader, VERTEX_SHADER_SOURCE);
-        gl.compile_shader(&vertex_shader);
-
-        let fragment_shader = gl.create_shader(GL::FRAGMENT_SHADER).unwrap_throw();
-        gl.shader_source(&fragment_shader, FRAGMENT_SHADER_SOURCE);
-        gl.compile_shader(&fragment_shader);
+        let vertex_shader = compile(&gl, GL::VERTEX_SHADER, VERTEX_SHADER_FILES)?;
+        let fragment_shader = compile(&gl, GL::FRAGMENT_SHADER, FRAGMENT_SHADER_FILES)
+            .inspect_err(|_| gl.delete_shader(Some(&vertex_shader)))?;
 
-        web_sys::console::log_1(&gl.get_shader_info_log(&vertex_shader).unwrap_throw().into());
-        web_sys::console::log_1(
-            &gl.get_shader_info_log(&fragment_shader)
-                .unwrap_throw()
-                .into(),
+        let program = Guard::new(
+            &gl,
+            gl.create_program().ok_or(Error::Create("program"))?,
+            GL::delete_program,
         );
-
-        let program = gl.create_program().unwrap_throw();
         gl.attach_shader(&program, &vertex_shader);
         gl.attach_shader(&program, &fragment_shader);
         gl.link_program(&program);
@@ -65,53 +80,123 @@ impl Renderer {
         gl.delete_shader(Some(&vertex_shader));
         gl.delete_shader(Some(&fragment_shader));
 
-        let attribute_color0 = gl.get_attrib_location(&program, "color0") as u32;
-        let attribute_color1 = gl.get_attrib_location(&program, "color1") as u32;
-        let attribute_color2 = gl.get_attrib_location(&program, "color2") as u32;
-        let attribute_color3 = gl.get_attrib_location(&program, "color3") as u32;
-        let attribute_color4 = gl.get_attrib_location(&program, "color4") as u32;
-        let attribute_color5 = gl.get_attrib_location(&program, "color5") as u32;
-
-        let attribute_pos = gl.get_attrib_location(&program, "pos") as u32;
-        let attribute_normal = gl.get_attrib_location(&program, "normal") as u32;
-        let attribute_center = gl.get_attrib_location(&program, "center") as u32;
-        let attribute_ambient = gl.get_attrib_location(&program, "ambient_factor") as u32;
-        let attribute_diffuse = gl.get_attrib_location(&program, "diffuse_factor") as u32;
+        if !gl
+            .get_program_parameter(&program, GL::LINK_STATUS)
+            .as_bool()
+            .unwrap_or(false)
+        {
+            return Err(Error::Link(
+                gl.get_program_info_log(&program).unwrap_or_default(),
+            ));
+        }
 
-        let vao_static = gl.create_vertex_array().unwrap_throw();
+        // The knot never changes, so its uniforms only need setting once.
+        let knot: Vec<f32> = polyline
+            .iter()
+            .flat_map(|&(pos, arc)| {
+                let arc = arc.map_or(-1., |arc| arc as f32);
+                vec![pos.x as f32, pos.y as f32, pos.z as f32, arc]
+            })
+            .collect();
+
+        gl.use_program(Some(&program));
+        let uniforms = Locations::new(&gl, &program);
+        gl.uniform4fv_with_f32_array(uniforms.get("knot"), &knot);
+        gl.uniform1i(uniforms.get("num_knot_vertices"), polyline.len() as i32);
+
+        // Row `2k` of the transition table is where passing under arc `k` from left to right takes each world,
+        // and row `2k + 1` is where passing under it from right to left does.
+        let transitions: Vec<u8> = (0..portal.knot.arcs())
+            .flat_map(|arc| vec![portal.permutation(arc), portal.inverse(arc)])
+            .flatten()
+            .map(|&world| world as u8)
+            .collect();
+
+        let transition_table = Guard::new(
+            &gl,
+            gl.create_texture().ok_or(Error::Create("texture"))?,
+            GL::delete_texture,
+        );
+        gl.active_texture(GL::TEXTURE0);
+        gl.bind_texture(GL::TEXTURE_2D, Some(&transition_table));
+        gl.pixel_storei(GL::UNPACK_ALIGNMENT, 1);
+        gl.tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
+            GL::TEXTURE_2D,
+            0,
+            GL::R8UI as i32,
+            portal.worlds(),
+            2 * portal.knot.arcs() as i32,
+            0,
+            GL::RED_INTEGER,
+            GL::UNSIGNED_BYTE,
+            Some(&transitions),
+        )
+        .map_err(Error::Upload)?;
+        gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_MIN_FILTER, GL::NEAREST as i32);
+        gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_MAG_FILTER, GL::NEAREST as i32);
+        gl.uniform1i(uniforms.get("transitions"), 0);
+
+        // Row `r` of the palette texture is the colour, in each world, of the triangles whose vertices have `palette = r`.
+        // The static geometry's palettes come first, and the dynamic geometry's are put after them every frame.
+        let palette_texture = Guard::new(
+            &gl,
+            gl.create_texture().ok_or(Error::Create("texture"))?,
+            GL::delete_texture,
+        );
+        gl.active_texture(GL::TEXTURE1);
+        gl.bind_texture(GL::TEXTURE_2D, Some(&palette_texture));
+        gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_MIN_FILTER, GL::NEAREST as i32);
+        gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_MAG_FILTER, GL::NEAREST as i32);
+        gl.uniform1i(uniforms.get("palettes"), 1);
+
+        let mut palettes = Palettes::new(portal.worlds());
+
+        // Point the attributes at the vertex buffer that's bound.
+        let point_attributes = || {
+            let mut offset = 0;
+            for &(name, floats) in ATTRIBUTES {
+                let location = gl.get_attrib_location(&program, name);
+                // The compiler may have optimised it away.
+                if location >= 0 {
+                    gl.enable_vertex_attrib_array(location as u32);
+                    gl.vertex_attrib_pointer_with_i32(
+                        location as u32,
+                        floats as i32,
+                        GL::FLOAT,
+                        false,
+                        VERTEX_FLOATS as i32 * 4,
+                        offset as i32 * 4,
+                    );
+                }
+                offset += floats;
+            }
+        };
+
+        let vao_static = Guard::new(
+            &gl,
+            gl.create_vertex_array()
+                .ok_or(Error::Create("vertex array"))?,
+            GL::delete_vertex_array,
+        );
         gl.bind_vertex_array(Some(&vao_static));
 
-        let vertex_buffer_static = gl.create_buffer().unwrap_throw();
+        let vertex_buffer_static = Guard::new(
+            &gl,
+            gl.create_buffer().ok_or(Error::Create("buffer"))?,
+            GL::delete_buffer,
+        );
         gl.bind_buffer(GL::ARRAY_BUFFER, Some(&vertex_buffer_static));
 
-        gl.enable_vertex_attrib_array(attribute_color0);
-        gl.vertex_attrib_pointer_with_i32(attribute_color0, 4, GL::FLOAT, false, 35 * 4, 0);
-        gl.enable_vertex_attrib_array(attribute_color1);
-        gl.vertex_attrib_pointer_with_i32(attribute_color1, 4, GL::FLOAT, false, 35 * 4, 4 * 4);
-        gl.enable_vertex_attrib_array(attribute_color2);
-        gl.vertex_attrib_pointer_with_i32(attribute_color2, 4, GL::FLOAT, false, 35 * 4, 8 * 4);
-        gl.enable_vertex_attrib_array(attribute_color3);
-        gl.vertex_attrib_pointer_with_i32(attribute_color3, 4, GL::FLOAT, false, 35 * 4, 12 * 4);
-        gl.enable_vertex_attrib_array(attribute_color4);
-        gl.vertex_attrib_pointer_with_i32(attribute_color4, 4, GL::FLOAT, false, 35 * 4, 16 * 4);
-        gl.enable_vertex_attrib_array(attribute_color5);
-        gl.vertex_attrib_pointer_with_i32(attribute_color5, 4, GL::FLOAT, false, 35 * 4, 20 * 4);
-
-        gl.enable_vertex_attrib_array(attribute_pos);
-        gl.vertex_attrib_pointer_with_i32(attribute_pos, 3, GL::FLOAT, false, 35 * 4, 24 * 4);
-        gl.enable_vertex_attrib_array(attribute_normal);
-        gl.vertex_attrib_pointer_with_i32(attribute_normal, 3, GL::FLOAT, false, 35 * 4, 27 * 4);
-        gl.enable_vertex_attrib_array(attribute_center);
-        gl.vertex_attrib_pointer_with_i32(attribute_center, 3, GL::FLOAT, false, 35 * 4, 30 * 4);
-        gl.enable_vertex_attrib_array(attribute_ambient);
-        gl.vertex_attrib_pointer_with_i32(attribute_ambient, 1, GL::FLOAT, false, 35 * 4, 33 * 4);
-        gl.enable_vertex_attrib_array(attribute_diffuse);
-        gl.vertex_attrib_pointer_with_i32(attribute_diffuse, 1, GL::FLOAT, false, 35 * 4, 34 * 4);
+        point_attributes();
 
         let data: Vec<f32> = static_geometry
             .into_iter()
-            .flat_map(triangle_to_array)
+            .flat_map(|tri| {
+                let row = palettes.row(&tri.colors);
+                triangle_to_array(tri, row)
+            })
             .collect::<Vec<f32>>();
+        palettes.upload(&gl)?;
 
         gl.buffer_data_with_array_buffer_view(
             GL::ARRAY_BUFFER,
@@ -119,34 +204,21 @@ impl Renderer {
             GL::STATIC_DRAW,
         );
 
-        let vao_dynamic = gl.create_vertex_array().unwrap_throw();
+        let vao_dynamic = Guard::new(
+            &gl,
+            gl.create_vertex_array()
+                .ok_or(Error::Create("vertex array"))?,
+            GL::delete_vertex_array,
+        );
         gl.bind_vertex_array(Some(&vao_dynamic));
-        let vertex_buffer_dynamic = gl.create_buffer().unwrap_throw();
+        let vertex_buffer_dynamic = Guard::new(
+            &gl,
+            gl.create_buffer().ok_or(Error::Create("buffer"))?,
+            GL::delete_buffer,
+        );
         gl.bind_buffer(GL::ARRAY_BUFFER, Some(&vertex_buffer_dynamic));
 
-        gl.enable_vertex_attrib_array(attribute_color0);
-        gl.vertex_attrib_pointer_with_i32(attribute_color0, 4, GL::FLOAT, false, 35 * 4, 0);
-        gl.enable_vertex_attrib_array(attribute_color1);
-        gl.vertex_attrib_pointer_with_i32(attribute_color1, 4, GL::FLOAT, false, 35 * 4, 4 * 4);
-        gl.enable_vertex_attrib_array(attribute_color2);
-        gl.vertex_attrib_pointer_with_i32(attribute_color2, 4, GL::FLOAT, false, 35 * 4, 8 * 4);
-        gl.enable_vertex_attrib_array(attribute_color3);
-        gl.vertex_attrib_pointer_with_i32(attribute_color3, 4, GL::FLOAT, false, 35 * 4, 12 * 4);
-        gl.enable_vertex_attrib_array(attribute_color4);
-        gl.vertex_attrib_pointer_with_i32(attribute_color4, 4, GL::FLOAT, false, 35 * 4, 16 * 4);
-        gl.enable_vertex_attrib_array(attribute_color5);
-        gl.vertex_attrib_pointer_with_i32(attribute_color5, 4, GL::FLOAT, false, 35 * 4, 20 * 4);
-
-        gl.enable_vertex_attrib_array(attribute_pos);
-        gl.vertex_attrib_pointer_with_i32(attribute_pos, 3, GL::FLOAT, false, 35 * 4, 24 * 4);
-        gl.enable_vertex_attrib_array(attribute_normal);
-        gl.vertex_attrib_pointer_with_i32(attribute_normal, 3, GL::FLOAT, false, 35 * 4, 27 * 4);
-        gl.enable_vertex_attrib_array(attribute_center);
-        gl.vertex_attrib_pointer_with_i32(attribute_center, 3, GL::FLOAT, false, 35 * 4, 30 * 4);
-        gl.enable_vertex_attrib_array(attribute_ambient);
-        gl.vertex_attrib_pointer_with_i32(attribute_ambient, 1, GL::FLOAT, false, 35 * 4, 33 * 4);
-        gl.enable_vertex_attrib_array(attribute_diffuse);
-        gl.vertex_attrib_pointer_with_i32(attribute_diffuse, 1, GL::FLOAT, false, 35 * 4, 34 * 4);
+        point_attributes();
 
         gl.buffer_data_with_array_buffer_view(
             GL::ARRAY_BUFFER,
@@ -154,8 +226,19 @@ impl Renderer {
             GL::DYNAMIC_DRAW,
         );
 
-        Self {
+        // Nothing can fail now, so the renderer's `Drop` takes over from the guards.
+        let program = program.keep();
+        let vao_static = vao_static.keep();
+        let vertex_buffer_static = vertex_buffer_static.keep();
+        let vao_dynamic = vao_dynamic.keep();
+        let vertex_buffer_dynamic = vertex_buffer_dynamic.keep();
+        let transition_table = transition_table.keep();
+        let palette_texture = palette_texture.keep();
+
+        Ok(Self {
             program,
+            uniforms,
+            palettes,
 
             vao_static,
             vertex_buffer_static,
@@ -163,41 +246,30 @@ impl Renderer {
             vao_dynamic,
             vertex_buffer_dynamic,
 
+            transition_table,
+            palette_texture,
+
             gl,
             canvas: canvas.clone(),
 
-            num_verts_static: data.len() / 35,
-        }
+            num_verts_static: data.len() / VERTEX_FLOATS,
+        })
     }
 
-    pub fn render(&self, uniforms: Uniforms, mut dynamic_geometry: Vec<crate::modeling::Triangle>) {
-        let width = web_sys::window()
-            .unwrap_throw()
-            .inner_width()
-            .unwrap_throw()
-            .as_f64()
-            .unwrap_throw()
-            - 16.;
-        let height = web_sys::window()
-            .unwrap_throw()
-            .inner_height()
-            .unwrap_throw()
-            .as_f64()
-            .unwrap_throw()
-            - 16.;
-
-        self.canvas
-            .set_attribute("width", &format!("{}", width as i32))
-            .unwrap_throw();
-        self.canvas
-            .set_attribute("height", &format!("{}", height as i32))
-            .unwrap_throw();
+    pub fn render(
+        &self,
+        uniforms: knotted_portal_core::modeling::Uniforms,
+        mut dynamic_geometry: Vec<knotted_portal_core::modeling::Triangle>,
+    ) -> Result<(), Error> {
+        // The front-end keeps the canvas's size in step with the page's layout.
+        let width = self.canvas.width().max(1);
+        let height = self.canvas.height().max(1);
 
         self.gl.use_program(Some(&self.program));
 
         let projection_matrix: nalgebra::Matrix4<f32> = nalgebra::Matrix4::new_perspective(
             width as f32 / height as f32,
-            std::f32::consts::FRAC_PI_2,
+            uniforms.fov,
             0.01,
             200.,
         );
@@ -205,37 +277,21 @@ impl Renderer {
         let mat: nalgebra::Matrix4<f32> =
             projection_matrix * uniforms.player_isometry.inverse().to_homogeneous();
 
-        self.gl.uniform_matrix4fv_with_f32_array(
-            self.gl.get_uniform_location(&self.program, "mat").as_ref(),
-            false,
-            &mat.as_slice(),
-        );
-
-        self.gl.uniform3f(
-            self.gl.get_uniform_location(&self.program, "eye").as_ref(),
-            uniforms.player_isometry.translation.vector[0],
-            uniforms.player_isometry.translation.vector[1],
-            uniforms.player_isometry.translation.vector[2],
-        );
+        self.gl
+            .uniform_matrix4fv_with_f32_array(self.uniforms.get("mat"), false, mat.as_slice());
 
         self.gl.uniform3f(
-            self.gl.get_uniform_location(&self.program, "eye").as_ref(),
+            self.uniforms.get("eye"),
             uniforms.player_isometry.translation.vector[0],
             uniforms.player_isometry.translation.vector[1],
             uniforms.player_isometry.translation.vector[2],
         );
 
-        self.gl.uniform1i(
-            self.gl
-                .get_uniform_location(&self.program, "eye_world")
-                .as_ref(),
-            uniforms.player_world,
-        );
+        self.gl
+            .uniform1i(self.uniforms.get("eye_world"), uniforms.player_world);
 
         self.gl.uniform3f(
-            self.gl
-                .get_uniform_location(&self.program, "light_dir")
-                .as_ref(),
+            self.uniforms.get("light_dir"),
             uniforms.light_dir[0],
             uniforms.light_dir[1],
             uniforms.light_dir[2],
@@ -258,37 +314,135 @@ impl Renderer {
                     .to_bits(), // to_bits is monotonic on positive floats, so this is an easy way to ignore NaN.
             )
         });
+        let mut palettes = self.palettes.clone();
         let data: Vec<f32> = dynamic_geometry
             .into_iter()
-            .flat_map(triangle_to_array)
+            .flat_map(|tri| {
+                let row = palettes.row(&tri.colors);
+                triangle_to_array(tri, row)
+            })
             .collect::<Vec<f32>>();
+        if palettes.rows() > self.palettes.rows() {
+            self.gl.active_texture(GL::TEXTURE1);
+            palettes.upload(&self.gl)?;
+        }
         self.gl.buffer_data_with_array_buffer_view(
             GL::ARRAY_BUFFER,
             &as_f32_array(&data).into(),
             GL::DYNAMIC_DRAW,
         );
         self.gl
-            .draw_arrays(GL::TRIANGLES, 0, (data.len() / 35) as i32);
+            .draw_arrays(GL::TRIANGLES, 0, (data.len() / VERTEX_FLOATS) as i32);
+        Ok(())
+    }
+}
+
+// Deletes a GL object when `Renderer::new` returns early, so an error doesn't leak what was made before it.
+struct Guard<'a, T> {
+    gl: &'a GL,
+    object: Option<T>,
+    delete: fn(&GL, Option<&T>),
+}
+
+impl<'a, T> Guard<'a, T> {
+    fn new(gl: &'a GL, object: T, delete: fn(&GL, Option<&T>)) -> Self {
+        Self {
+            gl,
+            object: Some(object),
+            delete,
+        }
+    }
+
+    // Hands the object over to whoever deletes it from now on.
+    fn keep(mut self) -> T {
+        self.object.take().unwrap()
+    }
+}
+
+impl<T> std::ops::Deref for Guard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.object.as_ref().unwrap()
+    }
+}
+
+impl<T> Drop for Guard<'_, T> {
+    fn drop(&mut self) {
+        if let Some(object) = &self.object {
+            (self.delete)(self.gl, Some(object));
+        }
     }
 }
 
-pub struct Uniforms {
-    pub player_isometry: nalgebra::Isometry3<f32>, // Player space -> World Space
-    pub player_world: i32,
-    pub light_dir: nalgebra::Vector3<f32>,
+// The rows of the palette texture, each a colour per world, and the palette in each row.
+#[derive(Clone)]
+struct Palettes {
+    worlds: usize,
+    texels: Vec<f32>,
+    // By address, since the triangles that look alike share a palette. Holding on to them keeps the addresses from being reused.
+    rows: std::collections::HashMap<*const [f32; 4], usize>,
+    palettes: Vec<knotted_portal_core::modeling::Palette>,
 }
 
-fn triangle_to_array(tri: crate::modeling::Triangle) -> impl IntoIterator<Item = f32> {
+impl Palettes {
+    fn new(worlds: i32) -> Self {
+        Self {
+            worlds: worlds as usize,
+            texels: Vec::new(),
+            rows: std::collections::HashMap::new(),
+            palettes: Vec::new(),
+        }
+    }
+
+    fn rows(&self) -> usize {
+        self.texels.len() / (4 * self.worlds)
+    }
+
+    // The row `palette` is in, adding it if it's new.
+    fn row(&mut self, palette: &knotted_portal_core::modeling::Palette) -> usize {
+        let next = self.rows();
+        let row = *self.rows.entry(palette.as_ptr()).or_insert(next);
+        if row == next {
+            self.palettes.push(palette.clone());
+            // The worlds past the end of the palette see nothing.
+            for world in 0..self.worlds {
+                let color = palette.get(world).copied().unwrap_or_default();
+                self.texels.extend_from_slice(&color);
+            }
+        }
+        row
+    }
+
+    // Into the texture bound to the active texture unit.
+    fn upload(&self, gl: &GL) -> Result<(), Error> {
+        gl.tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_array_buffer_view(
+            GL::TEXTURE_2D,
+            0,
+            GL::RGBA32F as i32,
+            self.worlds as i32,
+            self.rows() as i32,
+            0,
+            GL::RGBA,
+            GL::FLOAT,
+            Some(&as_f32_array(&self.texels)),
+        )
+        .map_err(Error::Upload)
+    }
+}
+
+fn triangle_to_array(
+    tri: knotted_portal_core::modeling::Triangle,
+    palette: usize,
+) -> impl IntoIterator<Item = f32> {
     let [v1, v2, v3] = tri.vertices;
 
     let normal: nalgebra::Vector3<f32> = (v2 - v1).cross(&(v3 - v1)).normalize();
     let center: nalgebra::Vector3<f32> = tri.center();
 
-    let mut out = Vec::with_capacity(3 * 35);
+    let mut out = Vec::with_capacity(3 * VERTEX_FLOATS);
     for &pos in &tri.vertices {
-        for &color in &tri.colors {
-            out.extend_from_slice(&color);
-        }
+        out.push(palette as f32);
         out.extend_from_slice(pos.as_slice());
         out.extend_from_slice(normal.as_slice());
         out.extend_from_slice(center.as_slice());
@@ -299,13 +453,183 @@ fn triangle_to_array(tri: crate::modeling::Triangle) -> impl IntoIterator<Item =
     out
 }
 
-const VERTEX_SHADER_SOURCE: &str = include_str!("shaders/vertex.glsl");
-const FRAGMENT_SHADER_SOURCE: &str = concat!(
-    include_str!("shaders/fragment_prelude.glsl"),
-    include_str!("shaders/quartic.glsl"),
-    include_str!("shaders/portal.glsl"),
-    include_str!("shaders/fragment.glsl"),
-);
+// The vertex shader's inputs, in the order `triangle_to_array` lays them out, with how many floats each takes.
+const ATTRIBUTES: &[(&str, usize)] = &[
+    ("palette", 1),
+    ("pos", 3),
+    ("normal", 3),
+    ("center", 3),
+    ("ambient_factor", 1),
+    ("diffuse_factor", 1),
+];
+
+const VERTEX_FLOATS: usize = {
+    let mut floats = 0;
+    let mut i = 0;
+    while i < ATTRIBUTES.len() {
+        floats += ATTRIBUTES[i].1;
+        i += 1;
+    }
+    floats
+};
+
+// Every uniform the renderer sets. Each is looked up once, when it starts.
+const UNIFORMS: &[&str] = &[
+    "mat",
+    "eye",
+    "eye_world",
+    "light_dir",
+    "knot",
+    "num_knot_vertices",
+    "transitions",
+    "palettes",
+];
+
+// Where each of `UNIFORMS` is in the program. The ones the compiler optimised away are missing, and setting them does nothing.
+struct Locations(std::collections::HashMap<&'static str, web_sys::WebGlUniformLocation>);
+
+impl Locations {
+    fn new(gl: &GL, program: &web_sys::WebGlProgram) -> Self {
+        Self(
+            UNIFORMS
+                .iter()
+                .filter_map(|&name| Some((name, gl.get_uniform_location(program, name)?)))
+                .collect(),
+        )
+    }
+
+    fn get(&self, name: &str) -> Option<&web_sys::WebGlUniformLocation> {
+        debug_assert!(UNIFORMS.contains(&name), "{} isn't in `UNIFORMS`", name);
+        self.0.get(name)
+    }
+}
+
+// Each shader's source is these files, one after another. Each must end with a newline.
+const VERTEX_SHADER_FILES: &[(&str, &str)] =
+    &[("vertex.glsl", include_str!("shaders/vertex.glsl"))];
+const FRAGMENT_SHADER_FILES: &[(&str, &str)] = &[
+    (
+        "fragment_prelude.glsl",
+        include_str!("shaders/fragment_prelude.glsl"),
+    ),
+    ("portal.glsl", include_str!("shaders/portal.glsl")),
+    ("fragment.glsl", include_str!("shaders/fragment.glsl")),
+];
+
+/// Why the renderer couldn't start.
+#[derive(Debug)]
+pub enum Error {
+    // The browser doesn't support WebGL 2, or has it turned off.
+    NoWebGl2,
+    // The info log, with its line numbers pointed at the files the lines came from.
+    Compile { shader: &'static str, log: String },
+    Link(String),
+    // The transition table has a byte per world.
+    TooManyWorlds(i32),
+    // The knot's undercrossings leave too little of the shader's polyline for the rest of it.
+    TooManyCrossings(usize),
+    // Making something on the GPU gave nothing back, which happens when the context is lost while starting.
+    Create(&'static str),
+    // Filling a texture was refused.
+    Upload(JsValue),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Error::NoWebGl2 => write!(f, "This browser doesn't support WebGL 2."),
+            Error::Compile { shader, log } => {
+                write!(f, "The {} shader didn't compile:\n{}", shader, log)
+            }
+            Error::Link(log) => write!(f, "The shaders didn't link:\n{}", log),
+            Error::TooManyWorlds(worlds) => write!(
+                f,
+                "The portal has {} worlds, but the renderer can only tell 256 apart.",
+                worlds
+            ),
+            Error::TooManyCrossings(crossings) => write!(
+                f,
+                "The knot has {} crossings, too many for the renderer's polyline of it.",
+                crossings
+            ),
+            Error::Create(what) => write!(f, "Couldn't make a WebGL {}.", what),
+            Error::Upload(e) => write!(f, "Couldn't fill a WebGL texture: {:?}", e),
+        }
+    }
+}
+
+fn compile(
+    gl: &GL,
+    kind: u32,
+    files: &[(&'static str, &str)],
+) -> Result<web_sys::WebGlShader, Error> {
+    let source: String = files.iter().map(|&(_, source)| source).collect();
+    let shader = gl.create_shader(kind).ok_or(Error::Create("shader"))?;
+    gl.shader_source(&shader, &source);
+    gl.compile_shader(&shader);
+
+    if gl
+        .get_shader_parameter(&shader, GL::COMPILE_STATUS)
+        .as_bool()
+        .unwrap_or(false)
+    {
+        return Ok(shader);
+    }
+    let log = gl.get_shader_info_log(&shader).unwrap_or_default();
+    gl.delete_shader(Some(&shader));
+    Err(Error::Compile {
+        shader: if kind == GL::VERTEX_SHADER {
+            "vertex"
+        } else {
+            "fragment"
+        },
+        log: annotate(&log, files),
+    })
+}
+
+// Point each `0:LINE:` in a shader's info log, which counts lines of the whole source, at the file and line it came from,
+// and quote that line.
+fn annotate(log: &str, files: &[(&'static str, &str)]) -> String {
+    let mut out = String::new();
+    for message in log.lines() {
+        let located = message.find("0:").and_then(|start| {
+            let rest = &message[start + 2..];
+            let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+            if !rest[digits..].starts_with(':') {
+                return None;
+            }
+            let (file, line, text) = locate(files, rest[..digits].parse().ok()?)?;
+            Some(format!(
+                "{}{}:{}{}\n    {}",
+                &message[..start],
+                file,
+                line,
+                &rest[digits..],
+                text.trim()
+            ))
+        });
+        out += &located.unwrap_or_else(|| message.to_string());
+        out += "\n";
+    }
+    out
+}
+
+// Which file line `line` of the whole source came from, its line in that file, and the line itself. Lines count from 1.
+fn locate<'a>(
+    files: &[(&'static str, &'a str)],
+    line: usize,
+) -> Option<(&'static str, usize, &'a str)> {
+    let mut skipped = 0;
+    for &(file, source) in files {
+        let lines = source.lines().count();
+        if line > skipped && line <= skipped + lines {
+            let n = line - skipped;
+            return Some((file, n, source.lines().nth(n - 1)?));
+        }
+        skipped += lines;
+    }
+    None
+}
 
 fn as_f32_array(v: &[f32]) -> js_sys::Float32Array {
     let memory_buffer = wasm_bindgen::memory()
@@ -317,3 +641,151 @@ fn as_f32_array(v: &[f32]) -> js_sys::Float32Array {
 
     js_sys::Float32Array::new(&memory_buffer).subarray(location, location + v.len() as u32)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn errors_point_at_files() {
+        let files = &[("a.glsl", "one\ntwo\n"), ("b.glsl", "three\n  four\n")];
+        assert_eq!(locate(files, 2), Some(("a.glsl", 2, "two")));
+        assert_eq!(locate(files, 4), Some(("b.glsl", 2, "  four")));
+        assert_eq!(locate(files, 5), None);
+
+        assert_eq!(
+            annotate(
+                "ERROR: 0:4: 'four' : undeclared identifier\nERROR: 1 compilation errors.",
+                files
+            ),
+            "ERROR: b.glsl:2: 'four' : undeclared identifier\n    four\nERROR: 1 compilation errors.\n"
+        );
+    }
+
+    // naga only reads GLSL as Vulkan has it, so rewrite ours, line by line, without changing what it does:
+    // each input and output gets a location, the uniforms go in a block, and each texture is split from its sampler.
+    // The declarations are each on a line of their own, at the start, as they are in our shaders.
+    fn vulkan(files: &[(&'static str, &str)]) -> String {
+        let source: String = files.iter().map(|&(_, source)| source).collect();
+        let lines: Vec<&str> = source.lines().collect();
+        let is_uniform = |line: &&str| line.starts_with("uniform ") && !line.contains("sampler");
+        // The block goes where the last uniform was, after the constants the uniforms use.
+        let last_uniform = lines.iter().rposition(is_uniform);
+
+        let mut out = String::from("#version 450\n");
+        let mut uniforms = String::new();
+        let mut locations = [0, 0];
+        let mut bindings = 1;
+        for (i, line) in lines.iter().enumerate() {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match words.first() {
+                Some(&"#version") | Some(&"precision") => {}
+                Some(&"in") | Some(&"out") | Some(&"flat") => {
+                    let words = &words[(words[0] == "flat") as usize..];
+                    let k = (words[0] == "out") as usize;
+                    out += &format!("layout(location = {}) {}", locations[k], line);
+                    // An array takes a location for each element.
+                    locations[k] += words[1]
+                        .split('[')
+                        .nth(1)
+                        .map_or(1, |n| n.trim_end_matches(']').parse::<u32>().unwrap());
+                }
+                Some(&"uniform") if line.contains("sampler2D") => {
+                    let name = words.last().unwrap().trim_end_matches(';');
+                    let u = if line.contains("usampler2D") { "u" } else { "" };
+                    out += &format!(
+                        "layout(set = 0, binding = {b}) uniform {u}texture2D {n}_texture; \
+                         layout(set = 0, binding = {s}) uniform sampler {n}_sampler;\n\
+                         #define {n} {u}sampler2D({n}_texture, {n}_sampler)",
+                        b = bindings,
+                        s = bindings + 1,
+                        u = u,
+                        n = name
+                    );
+                    bindings += 2;
+                }
+                Some(&"uniform") => {
+                    uniforms += &format!("    {}\n", &line["uniform ".len()..]);
+                    if Some(i) == last_uniform {
+                        out += &format!(
+                            "layout(set = 0, binding = 0) uniform Uniforms {{\n{}}};",
+                            uniforms
+                        );
+                    }
+                }
+                _ => out += line,
+            }
+            out += "\n";
+        }
+        out
+    }
+
+    fn parse(stage: naga::ShaderStage, files: &[(&'static str, &str)]) -> naga::Module {
+        let source = vulkan(files);
+        let module = naga::front::glsl::Frontend::default()
+            .parse(&naga::front::glsl::Options::from(stage), &source)
+            .unwrap_or_else(|e| panic!("{}", e.emit_to_string(&source)));
+        naga::valid::Validator::new(
+            naga::valid::ValidationFlags::all(),
+            naga::valid::Capabilities::all(),
+        )
+        .validate(&module)
+        .unwrap_or_else(|e| panic!("{}", e.emit_to_string(&source)));
+        module
+    }
+
+    // The shaders parse and validate, and their attributes and uniforms are exactly `ATTRIBUTES` and `UNIFORMS`.
+    //
+    // naga checks them as the desktop GLSL 4.50 that `vulkan` turns them into, not as GLSL ES 3.00, which is what the browser compiles.
+    // Rules only ES has, such as on precision qualifiers, aren't checked here. A shader that breaks them shows its errors over the canvas.
+    #[test]
+    fn shaders_compile() {
+        let vertex = parse(naga::ShaderStage::Vertex, VERTEX_SHADER_FILES);
+        let fragment = parse(naga::ShaderStage::Fragment, FRAGMENT_SHADER_FILES);
+
+        let mut attributes: Vec<(&str, usize)> = vertex.entry_points[0]
+            .function
+            .arguments
+            .iter()
+            .filter_map(|argument| {
+                let floats = match vertex.types[argument.ty].inner {
+                    naga::TypeInner::Vector { size, .. } => size as usize,
+                    _ => 1,
+                };
+                Some((argument.name.as_deref()?, floats))
+            })
+            .collect();
+        let mut uniforms: Vec<&str> = Vec::new();
+        for module in &[&vertex, &fragment] {
+            for (_, variable) in module.global_variables.iter() {
+                match &variable.name {
+                    // Each sampler goes with a texture.
+                    Some(name) if variable.space == naga::AddressSpace::Handle => {
+                        if let Some(name) = name.strip_suffix("_texture") {
+                            uniforms.push(name);
+                        }
+                    }
+                    None if variable.space == naga::AddressSpace::Uniform => {
+                        if let naga::TypeInner::Struct { members, .. } =
+                            &module.types[variable.ty].inner
+                        {
+                            uniforms.extend(members.iter().filter_map(|m| m.name.as_deref()));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let mut wanted = ATTRIBUTES.to_vec();
+        wanted.sort();
+        attributes.sort();
+        assert_eq!(attributes, wanted);
+
+        let mut wanted = UNIFORMS.to_vec();
+        wanted.sort();
+        uniforms.sort();
+        uniforms.dedup();
+        assert_eq!(uniforms, wanted);
+    }
+}
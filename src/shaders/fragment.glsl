@@ -4,7 +4,7 @@ void main() {
 	travel(world, eye, v_pos);
 	travel(world, v_pos, v_center);
 
-	color = v_colors[world];
+	color = texelFetch(palettes, ivec2(world, v_palette), 0);
 
 	color.rgb *= v_ambient_factor + v_diffuse_factor * max(dot(v_normal, light_dir), 0.0);
 }
@@ -2,10 +2,10 @@
 
 precision mediump float;
 
+flat in int v_palette;
 in vec3 v_pos;
 in vec3 v_normal;
 in vec3 v_center;
-in vec4[6] v_colors;
 in float v_ambient_factor;
 in float v_diffuse_factor;
 
@@ -14,3 +14,12 @@ out vec4 color;
 uniform vec3 eye;
 uniform int eye_world;
 uniform vec3 light_dir;
+
+const int KNOT_VERTICES = 144;
+
+uniform vec4 knot[KNOT_VERTICES];
+uniform int num_knot_vertices;
+uniform highp usampler2D transitions;
+
+// Row `v_palette` is the triangle's colour in each world.
+uniform highp sampler2D palettes;
@@ -1,11 +1,6 @@
 #version 300 es
 
-in vec4 color0;
-in vec4 color1;
-in vec4 color2;
-in vec4 color3;
-in vec4 color4;
-in vec4 color5;
+in float palette;
 in vec3 pos;
 in vec3 normal;
 in vec3 center;
@@ -14,20 +9,15 @@ in float diffuse_factor;
 
 uniform mat4 mat;
 
+flat out int v_palette;
 out vec3 v_pos;
 out vec3 v_normal;
 out vec3 v_center;
-out vec4[6] v_colors;
 out float v_ambient_factor;
 out float v_diffuse_factor;
 
 void main() {
-	v_colors[0] = color0;
-	v_colors[1] = color1;
-	v_colors[2] = color2;
-	v_colors[3] = color3;
-	v_colors[4] = color4;
-	v_colors[5] = color5;
+	v_palette = int(palette);
 
 	v_pos = pos;
 	v_normal = normal;
@@ -0,0 +1,163 @@
+use knotted_portal_core::replay::Input;
+use knotted_portal_core::touch::{Touches, JOYSTICK_RADIUS};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+pub enum Phase {
+    Start,
+    Move,
+    End,
+}
+
+const KNOB_RADIUS: f32 = 20.;
+const BUTTON_RADIUS: f32 = 32.;
+
+/// The on-screen controls for touch screens: the joystick, drawn while it's held, and the up and down buttons.
+/// They stay hidden until the screen is first touched.
+pub struct TouchControls {
+    touches: Touches,
+    ring: web_sys::HtmlElement,
+    knob: web_sys::HtmlElement,
+    pub up: web_sys::HtmlElement,
+    pub down: web_sys::HtmlElement,
+}
+
+impl TouchControls {
+    pub fn new(document: &web_sys::Document, parent: &web_sys::Element) -> Self {
+        let circle = |radius: f32, style: &str| {
+            overlay(
+                document,
+                parent,
+                &format!(
+                    "width: {d}px; height: {d}px; border-radius: {r}px; {}",
+                    style,
+                    d = 2. * radius,
+                    r = radius,
+                ),
+            )
+        };
+        Self {
+            touches: Touches::new(),
+            ring: circle(
+                JOYSTICK_RADIUS,
+                "border: 2px solid rgba(255, 255, 255, 0.5); margin: -2px; pointer-events: none;",
+            ),
+            knob: circle(
+                KNOB_RADIUS,
+                "background: rgba(255, 255, 255, 0.5); pointer-events: none;",
+            ),
+            up: button(circle(BUTTON_RADIUS, "right: 24px; bottom: 112px;"), "▲"),
+            down: button(circle(BUTTON_RADIUS, "right: 24px; bottom: 32px;"), "▼"),
+        }
+    }
+
+    /// Some changed fingers, at positions relative to the canvas.
+    pub fn touch(
+        &mut self,
+        phase: Phase,
+        fingers: Vec<(i32, [f32; 2])>,
+        canvas: &web_sys::DomRect,
+    ) -> Vec<Input> {
+        for button in &[&self.up, &self.down] {
+            button
+                .style()
+                .set_property("display", "block")
+                .unwrap_throw();
+        }
+
+        let mut out = Vec::new();
+        for (id, [x, y]) in fingers {
+            let pos = [x - canvas.left() as f32, y - canvas.top() as f32];
+            out.extend(match phase {
+                Phase::Start => self.touches.start(id, pos, canvas.width() as f32),
+                Phase::Move => self.touches.moved(id, pos),
+                Phase::End => self.touches.end(id),
+            });
+        }
+
+        match self.touches.joystick() {
+            Some((center, knob)) => {
+                place(&self.ring, center, JOYSTICK_RADIUS);
+                place(&self.knob, knob, KNOB_RADIUS);
+            }
+            None => {
+                for circle in &[&self.ring, &self.knob] {
+                    circle
+                        .style()
+                        .set_property("display", "none")
+                        .unwrap_throw();
+                }
+            }
+        }
+
+        out
+    }
+}
+
+/// The fingers that changed in a touch event, in client coordinates.
+pub fn fingers(evt: web_sys::Event) -> Vec<(i32, [f32; 2])> {
+    // Otherwise, the page scrolls and zooms, and the browser pretends the touch was a click.
+    evt.prevent_default();
+    let list = evt
+        .dyn_into::<web_sys::TouchEvent>()
+        .unwrap_throw()
+        .changed_touches();
+    (0..list.length())
+        .filter_map(|i| list.get(i))
+        .map(|t| (t.identifier(), [t.client_x() as f32, t.client_y() as f32]))
+        .collect()
+}
+
+// A hidden element floating over the canvas.
+fn overlay(
+    document: &web_sys::Document,
+    parent: &web_sys::Element,
+    style: &str,
+) -> web_sys::HtmlElement {
+    let element = document
+        .create_element("div")
+        .unwrap_throw()
+        .dyn_into::<web_sys::HtmlElement>()
+        .unwrap_throw();
+    element
+        .set_attribute(
+            "style",
+            &format!(
+                "position: absolute; display: none; touch-action: none; {}",
+                style
+            ),
+        )
+        .unwrap_throw();
+    parent.append_child(&element).unwrap_throw();
+    element
+}
+
+fn button(circle: web_sys::HtmlElement, label: &str) -> web_sys::HtmlElement {
+    circle.set_inner_text(label);
+    let style = circle.style();
+    for &(property, value) in &[
+        ("background", "rgba(255, 255, 255, 0.25)"),
+        ("color", "white"),
+        ("font", "24px sans-serif"),
+        ("text-align", "center"),
+        ("line-height", &format!("{}px", 2. * BUTTON_RADIUS)),
+        ("user-select", "none"),
+        // The overlay they're in lets the mouse and fingers through to the canvas.
+        ("pointer-events", "auto"),
+    ] {
+        style.set_property(property, value).unwrap_throw();
+    }
+    circle
+}
+
+// Show a circle centred on a point relative to the canvas.
+fn place(circle: &web_sys::HtmlElement, [x, y]: [f32; 2], radius: f32) {
+    let style = circle.style();
+    style.set_property("display", "block").unwrap_throw();
+    style
+        .set_property("left", &format!("{}px", x - radius))
+        .unwrap_throw();
+    style
+        .set_property("top", &format!("{}px", y - radius))
+        .unwrap_throw();
+}